@@ -1,49 +1,35 @@
 use crate::tetronimoe::{Figure, Direction};
-use crate::game_state::{FIELD_WIDTH, FIELD_HEIGHT};
+use crate::field::Field;
 
-pub fn direction_is_clear(figure: &Figure, direction: Direction, field: &Vec<bool>) -> bool {
-    let points = figure.tiles.clone();
-    for point in points {
-        match direction {
-            Direction::Left => {
-                if point.x == 0 {
-                    return false;
-                }
-
-                if field[(point.y * FIELD_WIDTH as isize + point.x - 1) as usize] {
-                    return false;
-                }
-            },
-            Direction::Right => {
-                if point.x == (FIELD_WIDTH - 1) as isize {
-                    return false;
-                }
-
-                if field[(point.y * FIELD_WIDTH as isize + point.x + 1) as usize] {
-                    return false;
-                }
-            },
-            Direction::Down => {
-                if point.y == (FIELD_HEIGHT - 1) as isize {
-                    return false;
-                }
-                if field[((point.y + 1) * FIELD_WIDTH as isize + point.x) as usize] {
-                    return false;
-                }
-            },
-            _ => unreachable!(),
-        }
+pub fn direction_is_clear(figure: &Figure, direction: Direction, field: &Field) -> bool {
+    match direction {
+        Direction::Up => offset_is_clear(figure, 0, -1, field),
+        Direction::Down => offset_is_clear(figure, 0, 1, field),
+        Direction::Left => offset_is_clear(figure, -1, 0, field),
+        Direction::Right => offset_is_clear(figure, 1, 0, field),
+        Direction::None => unreachable!(),
     }
+}
+
+pub fn position_is_clear(figure: &Figure, field: &Field) -> bool {
+    offset_is_clear(figure, 0, 0, field)
+}
 
-    return true;
+// Checks if figure can be placed with its tiles shifted by x and y
+pub fn offset_is_clear(figure: &Figure, x: isize, y: isize, field: &Field) -> bool {
+    figure.tiles.iter().all(|point| cell_is_free(point.x + x, point.y + y, field))
 }
 
-pub fn position_is_clear(figure: &Figure, field: &Vec<bool>) -> bool {
-    for point in figure.tiles.clone() {
-        if field[(point.y * FIELD_WIDTH as isize + point.x) as usize] {
-            return false;
-        }
+// Walls and floor are occupied, space above the field is free:
+// figures may stick out of the top during rotation.
+pub fn cell_is_free(x: isize, y: isize, field: &Field) -> bool {
+    if x < 0 || x >= field.width as isize || y >= field.height as isize {
+        return false;
+    }
+
+    if y < 0 {
+        return true;
     }
 
-    true
-}
\ No newline at end of file
+    !field.is_filled(x as usize, y as usize)
+}
@@ -0,0 +1,17 @@
+use crate::tetronimoe::BlockType;
+use crate::tspin::TSpin;
+use crate::game_state::{LockResult, GameOverReason};
+
+// Things that happen inside the engine and may be interesting for the outside world:
+// renderer, sounds, statistics, network. See GameState::drain_events.
+// Events of one lock come in order: PieceLocked, LinesCleared, PerfectClear, LevelUp, GarbageAdded, PieceSpawned, GameOver.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GameEvent {
+    PieceSpawned { block_type: BlockType },
+    PieceLocked { lock_result: LockResult },
+    LinesCleared { rows: Vec<usize>, tspin: TSpin }, // field rows as they were before the clear, top to bottom
+    PerfectClear { cleared_lines_count: u32 },
+    LevelUp { level: u32 },
+    GarbageAdded { rows: u32 }, // pending garbage came up from below
+    GameOver { reason: GameOverReason },
+}
@@ -0,0 +1,154 @@
+use crate::tetronimoe::Point;
+
+// Widest field a row mask can hold
+pub const MAX_FIELD_WIDTH: usize = 32;
+
+// Cells of the playing field, one bit mask per row: bit x is set if cell x of the row is occupied.
+// Row 0 is the top of the hidden buffer, like everywhere in the game.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Field {
+    pub width: usize, // at most MAX_FIELD_WIDTH
+    pub height: usize,
+    pub rows: Vec<u32>,
+}
+
+impl Field {
+    pub fn init(width: usize, height: usize) -> Field {
+        Field {
+            width: width.min(MAX_FIELD_WIDTH),
+            height,
+            rows: vec![0; height],
+        }
+    }
+
+    // Mask with all cells of a row occupied
+    pub fn get_full_row(&self) -> u32 {
+        u32::MAX.checked_shr((MAX_FIELD_WIDTH - self.width) as u32).unwrap_or(0)
+    }
+
+    pub fn is_filled(&self, x: usize, y: usize) -> bool {
+        self.rows[y] & (1 << x) != 0
+    }
+
+    pub fn set_filled(&mut self, x: usize, y: usize, filled: bool) {
+        if filled {
+            self.rows[y] |= 1 << x;
+        } else {
+            self.rows[y] &= !(1 << x);
+        }
+    }
+
+    pub fn line_is_full(&self, y: usize) -> bool {
+        self.rows[y] == self.get_full_row()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.iter().all(|row| *row == 0)
+    }
+
+    pub fn clear(&mut self) {
+        self.rows.iter_mut().for_each(|row| *row = 0);
+    }
+
+    // Removes full rows, everything above them falls down.
+    // Returns indices of cleared rows as they were before the clear, top to bottom.
+    pub fn clear_full_lines(&mut self) -> Vec<usize> {
+        let full_row = self.get_full_row();
+        let cleared_rows: Vec<usize> = (0..self.height).filter(|y| self.rows[*y] == full_row).collect();
+        if !cleared_rows.is_empty() {
+            self.rows.retain(|row| *row != full_row);
+            let mut rows = vec![0; cleared_rows.len()];
+            rows.append(&mut self.rows);
+            self.rows = rows;
+        }
+
+        cleared_rows
+    }
+
+    // Pushes all rows one row up and puts the given one at the bottom.
+    // Returns false if some blocks were pushed out of the top row, they are lost.
+    pub fn push_row_from_bottom(&mut self, row: u32) -> bool {
+        let top_row = self.rows.remove(0);
+        self.rows.push(row & self.get_full_row());
+        top_row == 0
+    }
+
+    // All occupied cells, row by row
+    pub fn get_filled_points(&self) -> Vec<Point> {
+        let mut points = vec![];
+        for (y, row) in self.rows.iter().enumerate() {
+            for x in (0..self.width).filter(|x| row & (1 << x) != 0) {
+                points.push(Point {x: x as isize, y: y as isize});
+            }
+        }
+
+        points
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fill_row(field: &mut Field, y: usize) {
+        for x in 0..field.width {
+            field.set_filled(x, y, true);
+        }
+    }
+
+    #[test]
+    fn cells() {
+        let mut field = Field::init(10, 4);
+        field.set_filled(9, 3, true);
+        assert!(field.is_filled(9, 3));
+        assert!(!field.is_filled(8, 3));
+        assert_eq!(field.rows[3], 1 << 9);
+        field.set_filled(9, 3, false);
+        assert!(field.is_empty());
+    }
+
+    #[test]
+    fn clear_full_lines() {
+        let mut field = Field::init(10, 6);
+        fill_row(&mut field, 2);
+        fill_row(&mut field, 4);
+        fill_row(&mut field, 5);
+        field.set_filled(1, 1, true);
+        field.set_filled(3, 3, true);
+
+        assert_eq!(field.clear_full_lines(), vec![2, 4, 5]);
+        assert_eq!(field.rows, vec![0, 0, 0, 0, 1 << 1, 1 << 3]);
+        assert_eq!(field.clear_full_lines(), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn push_row_from_bottom() {
+        let mut field = Field::init(4, 3);
+        field.set_filled(0, 1, true);
+
+        // cells outside of the field are dropped
+        assert!(field.push_row_from_bottom(0b1_1110));
+        assert_eq!(field.rows, vec![0b0001, 0, 0b1110]);
+
+        assert!(!field.push_row_from_bottom(0b0111));
+        assert_eq!(field.rows, vec![0, 0b1110, 0b0111]);
+    }
+
+    #[test]
+    fn widest_field() {
+        let mut field = Field::init(MAX_FIELD_WIDTH + 1, 3);
+        assert_eq!(field.width, MAX_FIELD_WIDTH);
+        assert_eq!(field.get_full_row(), u32::MAX);
+
+        fill_row(&mut field, 2);
+        field.set_filled(MAX_FIELD_WIDTH - 1, 1, true);
+        assert!(field.line_is_full(2));
+        assert!(!field.line_is_full(1));
+        assert_eq!(field.clear_full_lines(), vec![2]);
+        assert_eq!(field.rows, vec![0, 0, 1 << 31]);
+
+        assert!(field.push_row_from_bottom(u32::MAX));
+        assert!(field.line_is_full(2));
+        assert_eq!(field.get_filled_points().len(), MAX_FIELD_WIDTH + 1);
+    }
+}
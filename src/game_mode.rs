@@ -0,0 +1,241 @@
+use crate::game_state::{GameState, GameOverReason};
+use crate::timestep::TICKS_PER_SECOND;
+
+// Goal of the game: when it ends and how levels go up
+pub trait GameMode {
+    fn get_name(&self) -> &'static str;
+
+    // Lines to clear on this level to get to the next one
+    fn get_lines_per_level(&self, level: u32) -> u32;
+
+    // Level never goes above this one
+    fn get_max_level(&self) -> u32;
+
+    // Ticks the game lasts, None if it is not limited by time
+    fn get_time_limit(&self) -> Option<u64>;
+
+    // Reason to end the game right now, checked after every tick and every lock
+    fn check_goal(&self, state: &GameState) -> Option<GameOverReason>;
+
+    // If false, topping out does not end the game, the field is cleared instead
+    fn can_top_out(&self) -> bool;
+
+    // If true, the field starts with GameSettings::dig_rows garbage rows,
+    // they come up from below while there are less than DIG_ROWS_ON_FIELD of them
+    fn has_dig_garbage(&self) -> bool;
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum GameModeType {
+    Marathon, // 150 lines, 10 lines per level up to level 15
+    Sprint, // 40 lines as fast as possible
+    Ultra, // as many points as possible in 2 minutes
+    Zen, // endless, no game over
+    Dig, // clear all garbage rows as fast as possible
+}
+
+impl GameModeType {
+    pub fn create(&self) -> Box<dyn GameMode> {
+        match self {
+            GameModeType::Marathon => Box::new(MarathonMode {}),
+            GameModeType::Sprint => Box::new(SprintMode {}),
+            GameModeType::Ultra => Box::new(UltraMode {}),
+            GameModeType::Zen => Box::new(ZenMode {}),
+            GameModeType::Dig => Box::new(DigMode {}),
+        }
+    }
+
+    pub fn next(&self) -> GameModeType {
+        match self {
+            GameModeType::Marathon => GameModeType::Sprint,
+            GameModeType::Sprint => GameModeType::Ultra,
+            GameModeType::Ultra => GameModeType::Zen,
+            GameModeType::Zen => GameModeType::Dig,
+            GameModeType::Dig => GameModeType::Marathon,
+        }
+    }
+}
+
+pub const MARATHON_LINES: u32 = 150;
+pub const MARATHON_MAX_LEVEL: u32 = 15;
+pub const SPRINT_LINES: u32 = 40;
+pub const ULTRA_SECONDS: u64 = 120;
+pub const DIG_ROWS_ON_FIELD: u32 = 10;
+
+pub struct MarathonMode {}
+
+impl GameMode for MarathonMode {
+    fn get_name(&self) -> &'static str {
+        "Marathon"
+    }
+
+    fn get_lines_per_level(&self, _level: u32) -> u32 {
+        10
+    }
+
+    fn get_max_level(&self) -> u32 {
+        MARATHON_MAX_LEVEL
+    }
+
+    fn get_time_limit(&self) -> Option<u64> {
+        None
+    }
+
+    fn check_goal(&self, state: &GameState) -> Option<GameOverReason> {
+        if state.lines_cleared >= MARATHON_LINES {
+            Some(GameOverReason::Completed)
+        } else {
+            None
+        }
+    }
+
+    fn can_top_out(&self) -> bool {
+        true
+    }
+
+    fn has_dig_garbage(&self) -> bool {
+        false
+    }
+}
+
+// Level does not change, only time matters
+pub struct SprintMode {}
+
+impl GameMode for SprintMode {
+    fn get_name(&self) -> &'static str {
+        "Sprint 40L"
+    }
+
+    fn get_lines_per_level(&self, _level: u32) -> u32 {
+        u32::MAX
+    }
+
+    fn get_max_level(&self) -> u32 {
+        1
+    }
+
+    fn get_time_limit(&self) -> Option<u64> {
+        None
+    }
+
+    fn check_goal(&self, state: &GameState) -> Option<GameOverReason> {
+        if state.lines_cleared >= SPRINT_LINES {
+            Some(GameOverReason::Completed)
+        } else {
+            None
+        }
+    }
+
+    fn can_top_out(&self) -> bool {
+        true
+    }
+
+    fn has_dig_garbage(&self) -> bool {
+        false
+    }
+}
+
+pub struct UltraMode {}
+
+impl GameMode for UltraMode {
+    fn get_name(&self) -> &'static str {
+        "Ultra"
+    }
+
+    fn get_lines_per_level(&self, _level: u32) -> u32 {
+        u32::MAX
+    }
+
+    fn get_max_level(&self) -> u32 {
+        1
+    }
+
+    fn get_time_limit(&self) -> Option<u64> {
+        Some(ULTRA_SECONDS * TICKS_PER_SECOND as u64)
+    }
+
+    fn check_goal(&self, state: &GameState) -> Option<GameOverReason> {
+        match self.get_time_limit() {
+            Some(time_limit) if state.ticks >= time_limit => Some(GameOverReason::TimeUp),
+            _ => None,
+        }
+    }
+
+    fn can_top_out(&self) -> bool {
+        true
+    }
+
+    fn has_dig_garbage(&self) -> bool {
+        false
+    }
+}
+
+// The original endless game: level goes up every level * 5 lines
+pub struct ZenMode {}
+
+impl GameMode for ZenMode {
+    fn get_name(&self) -> &'static str {
+        "Zen"
+    }
+
+    fn get_lines_per_level(&self, level: u32) -> u32 {
+        level * 5
+    }
+
+    fn get_max_level(&self) -> u32 {
+        u32::MAX
+    }
+
+    fn get_time_limit(&self) -> Option<u64> {
+        None
+    }
+
+    fn check_goal(&self, _state: &GameState) -> Option<GameOverReason> {
+        None
+    }
+
+    fn can_top_out(&self) -> bool {
+        false
+    }
+
+    fn has_dig_garbage(&self) -> bool {
+        false
+    }
+}
+
+// Garbage rows come from below until all of them are cleared, only time and pieces matter
+pub struct DigMode {}
+
+impl GameMode for DigMode {
+    fn get_name(&self) -> &'static str {
+        "Dig"
+    }
+
+    fn get_lines_per_level(&self, _level: u32) -> u32 {
+        u32::MAX
+    }
+
+    fn get_max_level(&self) -> u32 {
+        1
+    }
+
+    fn get_time_limit(&self) -> Option<u64> {
+        None
+    }
+
+    fn check_goal(&self, state: &GameState) -> Option<GameOverReason> {
+        if state.garbage_rows_left == 0 {
+            Some(GameOverReason::Completed)
+        } else {
+            None
+        }
+    }
+
+    fn can_top_out(&self) -> bool {
+        true
+    }
+
+    fn has_dig_garbage(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,85 @@
+use crate::rotation_system::RotationSystemType;
+use crate::randomizer::RandomizerType;
+use crate::lock_delay::LockResetPolicy;
+use crate::scoring::ScoringType;
+use crate::speed_curve::SpeedCurveType;
+use crate::game_mode::GameModeType;
+use crate::piece_set::PieceSetType;
+use crate::field::MAX_FIELD_WIDTH;
+
+pub const MAX_PREVIEW_COUNT: usize = 6;
+// Smallest field where every tetromino fits in any orientation.
+// Piece sets with wider figures make the field wider, see PieceSet::get_widest_piece.
+pub const MIN_FIELD_WIDTH: usize = 4;
+pub const MIN_FIELD_HEIGHT: usize = 4;
+// Figures spawn in the lowest rows of the hidden buffer, right above the visible field
+pub const SPAWN_ROWS: usize = 2;
+
+// Rules of the game, chosen before the game starts and kept between restarts
+#[derive(Copy, Clone, Debug)]
+pub struct GameSettings {
+    pub game_mode: GameModeType,
+    pub rotation_system: RotationSystemType,
+    pub piece_set: PieceSetType,
+    pub randomizer: RandomizerType,
+    pub preview_count: usize, // how many upcoming figures are shown, 1..=MAX_PREVIEW_COUNT
+    pub show_ghost: bool, // draw where current figure will land
+    pub speed_curve: SpeedCurveType, // gravity, ARE and lock delay by level
+    pub lock_reset_policy: LockResetPolicy,
+    pub scoring: ScoringType,
+    pub field_width: usize, // in blocks, MIN_FIELD_WIDTH..=MAX_FIELD_WIDTH
+    pub field_height: usize, // visible rows, at least MIN_FIELD_HEIGHT
+    pub buffer_height: usize, // hidden rows above the visible field, at least SPAWN_ROWS
+    pub dig_rows: u32, // garbage rows to clear in dig mode
+    pub garbage_messiness: u32, // chance in percent that the hole moves on the next garbage row
+}
+
+impl GameSettings {
+    pub fn get_preview_count(&self) -> usize {
+        self.preview_count.clamp(1, MAX_PREVIEW_COUNT)
+    }
+
+    pub fn get_field_width(&self) -> usize {
+        self.field_width.clamp(MIN_FIELD_WIDTH, MAX_FIELD_WIDTH)
+    }
+
+    pub fn get_field_height(&self) -> usize {
+        self.field_height.max(MIN_FIELD_HEIGHT)
+    }
+
+    pub fn get_buffer_height(&self) -> usize {
+        self.buffer_height.max(SPAWN_ROWS)
+    }
+
+    // Field rows are counted from the top of the buffer,
+    // so the first visible row is get_buffer_height()
+    pub fn get_total_height(&self) -> usize {
+        self.get_buffer_height() + self.get_field_height()
+    }
+
+    // Top row of the spawn area
+    pub fn get_spawn_row(&self) -> usize {
+        self.get_buffer_height() - SPAWN_ROWS
+    }
+}
+
+impl Default for GameSettings {
+    fn default() -> GameSettings {
+        GameSettings {
+            game_mode: GameModeType::Marathon,
+            rotation_system: RotationSystemType::Srs,
+            piece_set: PieceSetType::Tetrominoes,
+            randomizer: RandomizerType::SevenBag,
+            preview_count: 5,
+            show_ghost: true,
+            speed_curve: SpeedCurveType::Guideline,
+            lock_reset_policy: LockResetPolicy::MoveReset,
+            scoring: ScoringType::Guideline,
+            field_width: 10,
+            field_height: 20,
+            buffer_height: 20,
+            dig_rows: 18,
+            garbage_messiness: 100,
+        }
+    }
+}
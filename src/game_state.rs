@@ -1,316 +1,920 @@
-use crate::tetronimoe::{Figure, Point, Direction};
+use crate::tetronimoe::{Figure, Direction, BlockType};
 use crate::collision_checker::{direction_is_clear, position_is_clear};
-use nalgebra::max;
+use crate::field::Field;
+use crate::rotation_system::RotationSystem;
+use crate::game_settings::GameSettings;
+use crate::lock_delay::LockDelay;
+use crate::randomizer::Randomizer;
+use crate::tspin::{TSpin, detect_tspin};
+use crate::scoring::is_difficult_clear;
+use crate::events::GameEvent;
+use crate::speed_curve::{SpeedCurve, LevelSpeed, INSTANT_GRAVITY};
+use crate::game_mode::{GameMode, DIG_ROWS_ON_FIELD};
+use crate::garbage::{GarbageGenerator, PendingGarbage};
+use crate::piece_set::PieceSet;
+use rand_chacha::ChaCha8Rng;
+use rand::SeedableRng;
+use std::collections::VecDeque;
+
+// Garbage holes use their own random sequence, not the one of figures
+const GARBAGE_SEED_OFFSET: u64 = 1;
+
+// Why the game is over
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum GameOverReason {
+    BlockOut, // new figure overlaps blocks right after spawn
+    LockOut, // figure was locked completely inside the hidden buffer
+    GarbageOut, // garbage pushed blocks above the top of the buffer
+    Completed, // goal of the game mode is reached
+    TimeUp, // time of the game mode is over
+}
 
-pub const FIELD_WIDTH : u32 = 10;
-pub const FIELD_HEIGHT : u32 = 22;
+impl GameOverReason {
+    pub fn is_top_out(&self) -> bool {
+        match self {
+            GameOverReason::BlockOut | GameOverReason::LockOut | GameOverReason::GarbageOut => true,
+            GameOverReason::Completed | GameOverReason::TimeUp => false,
+        }
+    }
+}
+
+// Final numbers of a finished game
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct GameResult {
+    pub reason: GameOverReason,
+    pub score: u64,
+    pub lines_cleared: u32,
+    pub pieces_locked: u32,
+    pub level: u32,
+    pub ticks: u64,
+}
+
+// What happened when figure was locked
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LockResult {
+    pub block_type: BlockType,
+    pub cleared_lines_count: u32,
+    pub tspin: TSpin,
+    pub combo: u32, // line clears in a row before this one
+    pub is_back_to_back: bool,
+    pub is_perfect_clear: bool, // field is empty after the clear
+    pub score: u64, // points for this lock, without drop points
+}
 
 // Typical game state
 pub struct GameState {
+    pub settings: GameSettings,
+    pub rotation_system: Box<dyn RotationSystem>,
+    pub piece_set: PieceSet, // figures the game is played with
+    pub game_mode: Box<dyn GameMode>,
+    pub randomizer: Box<dyn Randomizer>,
+    pub seed: u64, // same seed gives the same sequence of figures
+    rng: ChaCha8Rng, // all randomness of the game must come from here. Unlike StdRng, it gives the same numbers in every build
     pub is_paused: bool,
     pub current_score: u64,
     pub best_score: u64,
+    pub combo: u32, // locks in a row that cleared lines
+    pub back_to_back: bool, // last line clear was a Tetris or a T-spin
     pub level: u32,
     pub progress_to_next_level: u32,
-    pub filled_space: Vec<Point>, // todo remake, use something to count coordinates via formulae
-        // or use two-dimensional array
+    pub lines_cleared: u32, // since the start of the game
+    pub pieces_locked: u32, // since the start of the game
+    pub ticks: u64, // time since the start of the game, pauses are not counted
+    pub garbage_rows_left: u32, // dig mode: garbage rows on the field and still to come, not cleared yet
+    garbage_rows_to_add: u32, // dig mode: garbage rows that did not come up yet
+    dig_garbage_rows: Vec<usize>, // dig mode: field rows with dig garbage, received garbage is not there
+    garbage_generator: GarbageGenerator,
+    pub pending_garbage: VecDeque<PendingGarbage>, // received, but not yet on the field, the first one comes up first
+    pub field: Field, // locked blocks, figure in play is not there
     pub current_figure: Figure,
-    pub next_figure: Figure,
-    pub gravity: f64,
-    pub next_gravity_tick: i32,
+    pub next_queue: VecDeque<BlockType>, // upcoming figures, the first one goes next
+    pub hold_figure: Option<Figure>,
+    pub hold_is_used: bool, // hold can be used only once per drop
+    pub speed_curve: SpeedCurve,
+    pub gravity: f64, // rows per tick
+    pub gravity_progress: f64, // part of the row figure has already fallen
+    pub entry_delay_left: u32, // ARE: next figure is not in play until it is 0
+    pub is_soft_dropping: bool,
+    pub lock_delay: LockDelay,
+    pub last_move_was_rotation: bool, // needed for T-spin detection
+    pub last_rotation_kick: (isize, isize),
+    pub last_lock_result: Option<LockResult>,
+    pub game_over_reason: Option<GameOverReason>, // None while the game goes on
+    events: Vec<GameEvent>, // not yet taken by drain_events
 }
 
 impl GameState {
-    pub fn init() -> GameState {
-        GameState {
+    pub fn init(settings: GameSettings, seed: u64) -> GameState {
+        let piece_set = settings.piece_set.create();
+        let block_types = piece_set.get_block_types();
+        let rotation_system = settings.rotation_system.create_for_piece_set(&piece_set);
+        let game_mode = settings.game_mode.create();
+        let mut randomizer = settings.randomizer.create();
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let field = create_field(&settings, &piece_set);
+        let current_figure = Figure::spawn(randomizer.next_block_type(&block_types, &mut rng), field.width, settings.get_spawn_row(), rotation_system.as_ref());
+        let next_queue = (0..settings.get_preview_count()).map(|_| randomizer.next_block_type(&block_types, &mut rng)).collect();
+        let speed_curve = settings.speed_curve.create();
+        let speed = speed_curve.get_speed(1);
+        let mut state = GameState {
+            settings,
+            rotation_system,
+            piece_set,
+            game_mode,
+            randomizer,
+            seed,
+            rng,
+            current_figure,
+            next_queue,
             is_paused: false,
             current_score: 0,
             best_score: 0,
+            combo: 0,
+            back_to_back: false,
             level: 1,
             progress_to_next_level: 0,
-            filled_space: vec![],
-            current_figure: Figure::init_random(),
-            next_figure: Figure::init_random(),
-            gravity: GameState::get_level_gravity(1),
-            next_gravity_tick: (1.0 / GameState::get_level_gravity(1)) as i32,
-        }
+            lines_cleared: 0,
+            pieces_locked: 0,
+            ticks: 0,
+            garbage_rows_left: 0,
+            garbage_rows_to_add: 0,
+            dig_garbage_rows: vec![],
+            garbage_generator: GarbageGenerator::init(seed.wrapping_add(GARBAGE_SEED_OFFSET), settings.garbage_messiness),
+            pending_garbage: VecDeque::new(),
+            field,
+            hold_figure: None,
+            hold_is_used: false,
+            speed_curve,
+            gravity: speed.gravity,
+            gravity_progress: 0.0,
+            entry_delay_left: 0,
+            is_soft_dropping: false,
+            lock_delay: LockDelay::init(speed.lock_delay, settings.lock_reset_policy),
+            last_move_was_rotation: false,
+            last_rotation_kick: (0, 0),
+            last_lock_result: None,
+            game_over_reason: None,
+            events: vec![],
+        };
+        state.start_dig();
+        state.on_figure_spawned();
+        state
     }
 
+    // New game with a new random seed.
+    // Seeds are kept in u32 range, so they are short enough to be shared by hand.
     pub fn restart(&mut self) {
+        self.restart_with_seed(rand::random::<u32>() as u64);
+    }
+
+    pub fn restart_with_seed(&mut self, seed: u64) {
         if self.current_score > self.best_score {
             self.best_score = self.current_score;
         }
 
         self.current_score = 0;
+        self.combo = 0;
+        self.back_to_back = false;
         self.level = 1;
         self.progress_to_next_level = 0;
-        self.filled_space = vec![];
-        self.current_figure = Figure::init_random();
-        self.next_figure = Figure::init_random();
-        self.gravity = GameState::get_level_gravity(1);
-        self.next_gravity_tick = (1.0 / GameState::get_level_gravity(1)) as i32;
+        self.lines_cleared = 0;
+        self.pieces_locked = 0;
+        self.ticks = 0;
+        self.field = create_field(&self.settings, &self.piece_set);
+        self.garbage_generator = GarbageGenerator::init(seed.wrapping_add(GARBAGE_SEED_OFFSET), self.settings.garbage_messiness);
+        self.pending_garbage.clear();
+        self.seed = seed;
+        self.rng = ChaCha8Rng::seed_from_u64(seed);
+        self.randomizer = self.settings.randomizer.create();
+        let block_type = self.randomizer.next_block_type(&self.piece_set.get_block_types(), &mut self.rng);
+        self.current_figure = self.spawn_figure(block_type);
+        self.next_queue.clear();
+        self.fill_next_queue();
+        self.hold_figure = None;
+        self.hold_is_used = false;
+        let speed = self.get_level_speed();
+        self.gravity = speed.gravity;
+        self.gravity_progress = 0.0;
+        self.entry_delay_left = 0;
+        self.is_soft_dropping = false;
+        self.lock_delay = LockDelay::init(speed.lock_delay, self.settings.lock_reset_policy);
+        self.lock_delay.reset(self.current_figure.center.y);
+        self.last_move_was_rotation = false;
+        self.last_rotation_kick = (0, 0);
+        self.last_lock_result = None;
+        self.game_over_reason = None;
+        self.events.clear();
+        self.start_dig();
+        self.on_figure_spawned();
+    }
+
+    // Applies new rules, they take effect from the new game.
+    // Custom speed curve and piece set are kept unless another type of them is chosen.
+    pub fn restart_with_settings(&mut self, settings: GameSettings) {
+        if settings.speed_curve != self.settings.speed_curve {
+            self.speed_curve = settings.speed_curve.create();
+        }
+        if settings.piece_set != self.settings.piece_set {
+            self.piece_set = settings.piece_set.create();
+        }
+        self.settings = settings;
+        self.rotation_system = settings.rotation_system.create_for_piece_set(&self.piece_set);
+        self.game_mode = settings.game_mode.create();
+        self.restart();
+    }
+
+    // Speed curve that is not built in, e.g. loaded with SpeedCurve::load.
+    // Takes effect from the new game.
+    pub fn set_speed_curve(&mut self, speed_curve: SpeedCurve) {
+        self.speed_curve = speed_curve;
+        self.restart();
+    }
+
+    // Piece set that is not built in, e.g. loaded with PieceSet::load.
+    // Takes effect from the new game.
+    pub fn set_piece_set(&mut self, piece_set: PieceSet) {
+        self.rotation_system = self.settings.rotation_system.create_for_piece_set(&piece_set);
+        self.piece_set = piece_set;
+        self.restart();
+    }
+
+    pub fn get_level_speed(&self) -> LevelSpeed {
+        self.speed_curve.get_speed(self.level)
+    }
+
+    // Figure can't be moved during pause, ARE and after the game is over
+    pub fn figure_is_controllable(&self) -> bool {
+        !self.is_paused && !self.game_is_finished() && self.entry_delay_left == 0
     }
 
+    // Advances the game by one tick, see timestep::TICKS_PER_SECOND
     pub fn update(&mut self) {
-        if self.is_paused {
+        if self.is_paused || self.game_is_finished() {
             return ;
         }
 
-        if direction_is_clear(&self.current_figure, Direction::Down, &self.get_field_as_array()) {
-            if self.next_gravity_tick <= 0 {
-                self.current_figure.shift_down();
-                self.next_gravity_tick = (1.0 / self.gravity) as i32
+        self.ticks += 1;
+        if self.check_goal() {
+            return;
+        }
+
+        if self.entry_delay_left > 0 {
+            self.entry_delay_left -= 1;
+            if self.entry_delay_left == 0 {
+                self.on_figure_spawned();
+            }
+            return;
+        }
+
+        if direction_is_clear(&self.current_figure, Direction::Down, &self.field) {
+            if self.gravity_is_instant() {
+                self.fall(self.settings.get_total_height());
             } else {
-                self.next_gravity_tick -= 1;
+                self.gravity_progress += self.gravity;
+                let rows = self.gravity_progress.floor();
+                self.gravity_progress -= rows;
+                self.fall(rows as usize);
             }
-        } else {
-            self.filled_space.append(&mut self.current_figure.tiles.clone());
-            self.current_figure = self.next_figure.clone();
-            self.next_figure = Figure::init_random();
+        } else if self.lock_delay.tick() {
+            self.lock_current_figure();
         }
+    }
 
-        let cleared_lines_count = self.count_filled_lines();
-        self.clear_filled_lines();
-        self.update_score(cleared_lines_count);
-        self.update_level_progress(cleared_lines_count);
+    // Moves figure down by the given number of rows or until it hits the ground
+    fn fall(&mut self, rows: usize) {
+        for _ in 0..rows {
+            if !direction_is_clear(&self.current_figure, Direction::Down, &self.field) {
+                self.gravity_progress = 0.0;
+                return;
+            }
+
+            self.current_figure.shift_down();
+            if self.is_soft_dropping {
+                self.current_score += self.settings.scoring.get_soft_drop_score(1);
+            }
+            self.last_move_was_rotation = false;
+            self.lock_delay.on_step_down(self.current_figure.center.y);
+        }
     }
 
-    pub fn game_is_finished(&self) -> bool {
-        let start_position = Figure::get_start_position();
-        !position_is_clear(&self.current_figure, &self.get_field_as_array())
-            && self.current_figure.center.x == start_position.x
-            && self.current_figure.center.y == start_position.y
+    fn gravity_is_instant(&self) -> bool {
+        self.gravity >= INSTANT_GRAVITY
     }
 
-    pub fn rotate_clockwise(&mut self) {
-        let mut possible_position = self.current_figure.clone();
-        possible_position.rotate_cw();
-        match self.resolve_rotation_collision(possible_position) {
-            None => (),
-            Some(possible_position) => {
-                self.current_figure = possible_position;
-            }
+    // With instant gravity figure can't hang in the air even for a tick:
+    // it falls right after spawn and after every move, so it slides into holes on its way
+    fn apply_instant_gravity(&mut self) {
+        if self.gravity_is_instant() {
+            self.fall(self.settings.get_total_height());
         }
     }
 
-    pub fn rotate_counter_clockwise(&mut self) {
-        let mut possible_position = self.current_figure.clone();
-        possible_position.rotate_ccw();
-        match self.resolve_rotation_collision(possible_position) {
-            None => (),
-            Some(possible_position) => {
-                self.current_figure = possible_position;
-            }
+    // Current figure becomes part of the field, filled lines are cleared and next figure is spawned.
+    // Figure that does not reach the visible field (or sticks out of the buffer) ends the game.
+    fn lock_current_figure(&mut self) {
+        let buffer_height = self.settings.get_buffer_height() as isize;
+        if self.current_figure.tiles.iter().all(|point| point.y < buffer_height)
+            || self.current_figure.tiles.iter().any(|point| point.y < 0) {
+            self.finish_game(GameOverReason::LockOut);
+            return;
+        }
+
+        let tspin = detect_tspin(&self.current_figure, self.last_move_was_rotation, self.last_rotation_kick, &self.field);
+        for point in self.current_figure.tiles.iter() {
+            self.field.set_filled(point.x as usize, point.y as usize, true);
+        }
+
+        self.pieces_locked += 1;
+        let cleared_rows = self.field.clear_full_lines();
+        let cleared_lines_count = cleared_rows.len() as u32;
+        self.on_dig_rows_cleared(&cleared_rows);
+        let lock_result = self.update_score(cleared_lines_count, tspin);
+        self.last_lock_result = Some(lock_result);
+        self.events.push(GameEvent::PieceLocked { lock_result });
+        if cleared_lines_count > 0 {
+            self.events.push(GameEvent::LinesCleared { rows: cleared_rows, tspin });
+        }
+        if lock_result.is_perfect_clear {
+            self.events.push(GameEvent::PerfectClear { cleared_lines_count });
+        }
+        self.update_level_progress(cleared_lines_count);
+        self.refill_dig_garbage();
+        // line clears hold back received garbage
+        if cleared_lines_count == 0 {
+            self.add_pending_garbage();
+        }
+        if self.game_is_finished() || self.check_goal() {
+            return;
+        }
+
+        self.current_figure = self.take_next_figure();
+        self.hold_is_used = false;
+        self.last_move_was_rotation = false;
+        self.gravity_progress = 0.0;
+        self.lock_delay.reset(self.current_figure.center.y);
+
+        // next figure shows up after ARE, or right now if there is no ARE
+        let speed = self.get_level_speed();
+        self.entry_delay_left = speed.are;
+        if cleared_lines_count > 0 {
+            self.entry_delay_left += speed.line_clear_delay;
+        }
+        if self.entry_delay_left == 0 {
+            self.on_figure_spawned();
         }
     }
 
-    fn resolve_rotation_collision(&self, mut figure_position: Figure) -> Option<Figure> {
-        let points = figure_position.tiles.clone();
-        // check top left point
-        let mut top_left_point = points.first().unwrap().clone();
-        let mut top_right_point = points.first().unwrap().clone();
-        // check top right point
-        for point in points {
-            // lets just assume that we can't rotate figure if it is too low
-            if point.y < 0 {
-                return None;
-            }
+    // New figure appeared at the top, game is over if there is no room for it
+    fn on_figure_spawned(&mut self) {
+        self.events.push(GameEvent::PieceSpawned { block_type: self.current_figure.block_type });
+        if !position_is_clear(&self.current_figure, &self.field) {
+            self.finish_game(GameOverReason::BlockOut);
+            return;
+        }
 
-            if point.x < top_left_point.x {
-                top_left_point = point;
-            } else if point.x > top_right_point.x {
-                top_right_point = point;
-            }
+        self.apply_instant_gravity();
+    }
+
+    // Game modes without top out just clear the field and go on
+    fn finish_game(&mut self, reason: GameOverReason) {
+        if reason.is_top_out() && !self.game_mode.can_top_out() {
+            self.field.clear();
+            self.dig_garbage_rows.clear();
+            return;
         }
 
-        // right shift on two blocks
-        if top_left_point.x == -2 {
-            if direction_is_clear(&figure_position, Direction::Right, &self.get_field_as_array()) {
-                figure_position.shift_right();
-            } else {
-                return None;
-            }
-            return if direction_is_clear(&figure_position, Direction::Right, &self.get_field_as_array()) {
-                figure_position.shift_right();
-                Some(figure_position)
-            } else {
-                None
-            }
+        if self.current_score > self.best_score {
+            self.best_score = self.current_score;
         }
+        self.game_over_reason = Some(reason);
+        self.events.push(GameEvent::GameOver { reason });
+    }
 
-        // right shift on one block
-        if top_left_point.x == -1 {
-            return if direction_is_clear(&figure_position, Direction::Right, &self.get_field_as_array()) {
-                figure_position.shift_right();
-                Some(figure_position)
-            } else {
-                None
-            }
+    // Returns true if game is over because goal of the game mode is reached
+    fn check_goal(&mut self) -> bool {
+        match self.game_mode.check_goal(self) {
+            Some(reason) => {
+                self.finish_game(reason);
+                true
+            },
+            None => false,
         }
+    }
 
-        // left shift on one block
-        if top_right_point.x == FIELD_WIDTH as isize {
-            return if direction_is_clear(&figure_position, Direction::Left, &self.get_field_as_array()) {
-                figure_position.shift_left();
-                Some(figure_position)
-            } else {
-                None
-            }
+    // None while the game goes on
+    pub fn get_result(&self) -> Option<GameResult> {
+        self.game_over_reason.map(|reason| GameResult {
+            reason,
+            score: self.current_score,
+            lines_cleared: self.lines_cleared,
+            pieces_locked: self.pieces_locked,
+            level: self.level,
+            ticks: self.ticks,
+        })
+    }
+
+    // Ticks left for modes with time limit, otherwise ticks since the start
+    pub fn get_timer(&self) -> u64 {
+        match self.game_mode.get_time_limit() {
+            Some(time_limit) => time_limit.saturating_sub(self.ticks),
+            None => self.ticks,
         }
+    }
 
-        // left shift on two block
-        if top_right_point.x == (FIELD_WIDTH + 1) as isize {
-            if direction_is_clear(&figure_position, Direction::Left, &self.get_field_as_array()) {
-                figure_position.shift_left();
-            } else {
-                return None;
-            }
+    // Returns everything that happened since the last call.
+    // Events are kept until taken, so somebody has to call it regularly.
+    pub fn drain_events(&mut self) -> Vec<GameEvent> {
+        std::mem::take(&mut self.events)
+    }
 
-            return if direction_is_clear(&figure_position, Direction::Left, &self.get_field_as_array()) {
-                figure_position.shift_left();
-                Some(figure_position)
-            } else {
-                None
-            }
+    // Upcoming figures in the order they will appear
+    pub fn peek_next(&self) -> Vec<BlockType> {
+        self.next_queue.iter().copied().collect()
+    }
+
+    fn take_next_figure(&mut self) -> Figure {
+        let block_type = self.next_queue.pop_front().unwrap();
+        self.fill_next_queue();
+        self.spawn_figure(block_type)
+    }
+
+    fn spawn_figure(&self, block_type: BlockType) -> Figure {
+        Figure::spawn(block_type, self.field.width, self.settings.get_spawn_row(), self.rotation_system.as_ref())
+    }
+
+    fn fill_next_queue(&mut self) {
+        let block_types = self.piece_set.get_block_types();
+        while self.next_queue.len() < self.settings.get_preview_count() {
+            self.next_queue.push_back(self.randomizer.next_block_type(&block_types, &mut self.rng));
+        }
+    }
+
+    // Position where current figure stops if it just falls down
+    pub fn get_landing_position(&self) -> Figure {
+        let mut landing_position = self.current_figure.clone();
+        while direction_is_clear(&landing_position, Direction::Down, &self.field) {
+            landing_position.shift_down();
+        }
+
+        landing_position
+    }
+
+    pub fn game_is_finished(&self) -> bool {
+        self.game_over_reason.is_some()
+    }
+
+    pub fn rotate_clockwise(&mut self) {
+        let mut possible_position = self.current_figure.clone();
+        possible_position.rotate_cw(self.rotation_system.as_ref());
+        self.apply_rotation(possible_position);
+    }
+
+    pub fn rotate_counter_clockwise(&mut self) {
+        let mut possible_position = self.current_figure.clone();
+        possible_position.rotate_ccw(self.rotation_system.as_ref());
+        self.apply_rotation(possible_position);
+    }
+
+    fn apply_rotation(&mut self, rotated_figure: Figure) {
+        let previous_direction = self.current_figure.direction;
+        let rotation_center = rotated_figure.center;
+        if !self.figure_is_controllable() {
+            return;
         }
 
-        return Some(figure_position);
+        if let Some(figure) = self.rotation_system.resolve_rotation(rotated_figure, previous_direction, &self.field) {
+            self.last_rotation_kick = (figure.center.x - rotation_center.x, figure.center.y - rotation_center.y);
+            self.last_move_was_rotation = true;
+            self.current_figure = figure;
+            self.lock_delay.on_move();
+            self.apply_instant_gravity();
+        }
     }
 
-    // Hard drop is 60 blocks per second.
-    // I know that it is incorrect, and it must be 20 blocks per frame.
-    // I'll fix it in the future, probably.
-    // Or not. I'm making this as an experiment, I can play with mechanics.
-    pub fn set_hard_drop_gravity(&mut self) {
-        self.gravity = 20.0;
-        self.next_gravity_tick = 0;
+    // Figure instantly falls to the landing position and locks there.
+    // Every block of the way gives 2 points.
+    pub fn hard_drop(&mut self) {
+        if !self.figure_is_controllable() {
+            return;
+        }
+
+        let landing_position = self.get_landing_position();
+        let drop_distance = landing_position.center.y - self.current_figure.center.y;
+        if drop_distance > 0 {
+            self.last_move_was_rotation = false;
+        }
+        self.current_figure = landing_position;
+        self.current_score += self.settings.scoring.get_hard_drop_score(drop_distance as u32);
+        self.lock_current_figure();
     }
 
-    // Soft drop speed is 20 blocks per second
-    pub fn set_soft_drop_gravity(&mut self) {
-        self.gravity = 0.3;
-        self.next_gravity_tick = 0;
+    // Figure falls factor times faster than normal, but not faster than a block per frame.
+    // Soft drop never slows down gravity that is already faster than that.
+    pub fn set_soft_drop_gravity(&mut self, factor: f64) {
+        let level_gravity = self.get_level_speed().gravity;
+        self.gravity = (level_gravity * factor).min(1.0).max(level_gravity);
+        // the first row goes right away
+        self.gravity_progress = 1.0;
+        self.is_soft_dropping = true;
     }
 
     pub fn reset_gravity(&mut self) {
-        self.gravity = GameState::get_level_gravity(self.level);
-        self.next_gravity_tick = (1.0 / GameState::get_level_gravity(self.level)) as i32;
+        self.is_soft_dropping = false;
+        self.gravity = self.get_level_speed().gravity;
+        self.gravity_progress = 0.0;
     }
 
-    fn get_level_gravity(level: u32) -> f64 {
-        ((0.8 - ((level as f32 - 1.0) * 0.007)).powf((level - 1) as f32)) as f64 / 60.0
+    // Gravity and lock delay follow the speed curve, soft drop keeps its own gravity
+    fn apply_level_speed(&mut self) {
+        let speed = self.get_level_speed();
+        self.lock_delay.delay = speed.lock_delay;
+        if !self.is_soft_dropping {
+            self.gravity = speed.gravity;
+        }
     }
 
-    pub fn left_shift(&mut self) {
-        if direction_is_clear(&self.current_figure, Direction::Left, &self.get_field_as_array()) {
+    // Returns false if figure can't be moved
+    pub fn left_shift(&mut self) -> bool {
+        if self.figure_is_controllable() && direction_is_clear(&self.current_figure, Direction::Left, &self.field) {
             self.current_figure.shift_left();
+            self.last_move_was_rotation = false;
+            self.lock_delay.on_move();
+            self.apply_instant_gravity();
+            return true;
         }
+
+        false
     }
 
-    pub fn right_shift(&mut self) {
-        if direction_is_clear(&self.current_figure, Direction::Right, &self.get_field_as_array()) {
+    pub fn right_shift(&mut self) -> bool {
+        if self.figure_is_controllable() && direction_is_clear(&self.current_figure, Direction::Right, &self.field) {
             self.current_figure.shift_right();
+            self.last_move_was_rotation = false;
+            self.lock_delay.on_move();
+            self.apply_instant_gravity();
+            return true;
         }
+
+        false
     }
 
     pub fn pause(&mut self) {
         self.is_paused = !self.is_paused;
     }
 
-    pub fn hold(&self) { //todo implement in later versions
-        unimplemented!()
+    // Puts current figure to the hold slot.
+    // Figure from the hold slot (or the next one, if slot is empty) starts falling from the top.
+    pub fn hold(&mut self) {
+        if self.hold_is_used || !self.figure_is_controllable() {
+            return;
+        }
+
+        let held_figure = self.spawn_figure(self.current_figure.block_type);
+        self.current_figure = match self.hold_figure.take() {
+            Some(figure) => self.spawn_figure(figure.block_type),
+            None => {
+                self.take_next_figure()
+            },
+        };
+        self.hold_figure = Some(held_figure);
+        self.hold_is_used = true;
+        self.last_move_was_rotation = false;
+        self.lock_delay.reset(self.current_figure.center.y);
+        self.on_figure_spawned();
     }
 
-    fn count_filled_lines(&self) -> u32 {
-        let field_as_array = self.get_field_as_array();
+    // Dig mode puts garbage under the empty field, nothing happens in other modes
+    fn start_dig(&mut self) {
+        if self.game_mode.has_dig_garbage() {
+            self.garbage_rows_left = self.settings.dig_rows;
+            self.garbage_rows_to_add = self.settings.dig_rows;
+        } else {
+            self.garbage_rows_left = 0;
+            self.garbage_rows_to_add = 0;
+        }
+        self.dig_garbage_rows.clear();
+        self.refill_dig_garbage();
+    }
 
-        let mut result = 0;
-        for i in 0..FIELD_HEIGHT {
-            let mut line_full = true;
-            for j in 0..FIELD_WIDTH {
-                line_full = line_full && field_as_array[(j + i * FIELD_WIDTH) as usize];
-            }
-            if line_full {
-                result += 1;
-            }
+    // Not more than DIG_ROWS_ON_FIELD garbage rows (or half of the field on small fields) are shown at once
+    fn refill_dig_garbage(&mut self) {
+        let max_rows_on_field = DIG_ROWS_ON_FIELD.min(self.settings.get_field_height() as u32 / 2);
+        while self.garbage_rows_to_add > 0 && (self.dig_garbage_rows.len() as u32) < max_rows_on_field {
+            let hole_column = self.garbage_generator.next_hole_column(self.field.width);
+            self.add_garbage_row(hole_column);
+            self.dig_garbage_rows.push(self.settings.get_total_height() - 1);
+            self.garbage_rows_to_add -= 1;
         }
+    }
 
-        return result;
+    // Rows above the cleared ones fall down, dig rows among the cleared ones are done
+    fn on_dig_rows_cleared(&mut self, cleared_rows: &[usize]) {
+        let dig_rows_before = self.dig_garbage_rows.len();
+        self.dig_garbage_rows.retain(|row| !cleared_rows.contains(row));
+        self.garbage_rows_left -= (dig_rows_before - self.dig_garbage_rows.len()) as u32;
+        for row in self.dig_garbage_rows.iter_mut() {
+            *row += cleared_rows.iter().filter(|cleared_row| **cleared_row > *row).count();
+        }
     }
 
-    fn clear_filled_lines(&mut self) {
-        // transform points to array
-        let mut field_as_array = self.get_field_as_array();
-        // go from top to bottom: y 0 -> 22
-        for i in 0..FIELD_HEIGHT {
-            let mut line_filled = true;
-            for j in 0..FIELD_WIDTH {
-                line_filled = line_filled && field_as_array[(j + i * FIELD_WIDTH) as usize];
-            }
-            // if line is fully filled
-            if line_filled {
-                // all lines above are shifted down
-                for k in (0..i+1).rev() {
-                    for l in 0..FIELD_WIDTH {
-                        if k == 0 {
-                            field_as_array[(l + k * FIELD_WIDTH) as usize] = false;
-                        } else {
-                            field_as_array[(l + k * FIELD_WIDTH) as usize] = field_as_array[(l + (k - 1) * FIELD_WIDTH) as usize];
-                        }
-                    }
-                }
-            }
+    // Pushes the whole stack one row up and puts a garbage row with one hole under it.
+    // Blocks pushed above the top of the buffer end the game.
+    fn add_garbage_row(&mut self, hole_column: usize) {
+        if self.game_is_finished() {
+            return;
         }
 
-        self.store_field_from_array(field_as_array);
+        // rows pushed out of the top are lost anyway
+        self.dig_garbage_rows.retain(|row| *row > 0);
+        for row in self.dig_garbage_rows.iter_mut() {
+            *row -= 1;
+        }
+
+        let garbage_row = self.field.get_full_row() & !(1 << hole_column);
+        if !self.field.push_row_from_bottom(garbage_row) {
+            self.finish_game(GameOverReason::GarbageOut);
+        }
     }
 
-    // true - cell is occupied with block
-    // false - cell is empty
-    pub fn get_field_as_array(&self) -> Vec<bool> {
-        let field_size = (FIELD_HEIGHT * FIELD_WIDTH) as usize;
-        // Field represented in consecutive rows: row0, row1,...
-        // Position:
-        //  - Point to array: index = Point.x + Point.y * FIELD_WIDTH
-        //  - Array to point: Point {x: index % FIELD_WIDTH, y: index / FIELD_WIDTH
-        let mut field_as_array: Vec<bool> = vec![false; field_size];
+    // Garbage waits in the queue and comes up after the next lock that clears no lines.
+    // Hole column outside of the field is moved to the last column.
+    pub fn receive_garbage(&mut self, rows: u32, hole_column: usize) {
+        if rows > 0 {
+            let hole_column = hole_column.min(self.field.width - 1);
+            self.pending_garbage.push_back(PendingGarbage { rows, hole_column });
+        }
+    }
 
-        for point in &self.filled_space {
-            field_as_array[(point.x + point.y * FIELD_WIDTH as isize) as usize] = true;
+    pub fn get_pending_garbage_rows(&self) -> u32 {
+        self.pending_garbage.iter().map(|garbage| garbage.rows).sum()
+    }
+
+    // Brings all pending garbage up right now, e.g. by timer in survival modes.
+    // Figure in play is pushed up if the stack rises into it.
+    pub fn raise_pending_garbage(&mut self) {
+        if self.game_is_finished() || self.add_pending_garbage() == 0 {
+            return;
         }
 
-        return field_as_array;
+        // figure waiting for ARE is checked on spawn
+        if self.entry_delay_left > 0 || self.game_is_finished() {
+            return;
+        }
+        while !position_is_clear(&self.current_figure, &self.field) {
+            if self.current_figure.tiles.iter().any(|point| point.y <= 0) {
+                self.finish_game(GameOverReason::GarbageOut);
+                return;
+            }
+            self.current_figure.shift_up();
+        }
     }
 
-    pub fn store_field_from_array(&mut self, array_field: Vec<bool>) {
-        let mut field_as_points = vec![];
-        for (index, element) in array_field.iter().enumerate() {
-            if *element {
-                field_as_points.push(Point {x: index as isize % FIELD_WIDTH as isize, y: index as isize / FIELD_WIDTH as isize});
+    // Returns the number of rows added
+    fn add_pending_garbage(&mut self) -> u32 {
+        let rows = self.get_pending_garbage_rows();
+        if rows == 0 {
+            return 0;
+        }
+
+        self.events.push(GameEvent::GarbageAdded { rows });
+        while let Some(garbage) = self.pending_garbage.pop_front() {
+            for _ in 0..garbage.rows {
+                self.add_garbage_row(garbage.hole_column);
             }
         }
+        rows
+    }
 
-        self.filled_space = field_as_points;
+    // Field must be as big as the game field: field.width x get_total_height()
+    pub fn store_field(&mut self, field: Field) {
+        self.field = field;
     }
 
-    fn update_score(&mut self, cleared_lines_count: u32) {
-        let score_base: u64 = match cleared_lines_count {
-            0 => 0,
-            1 => 40,
-            2 => 100,
-            3 => 300,
-            _ => 1200,
-        };
+    // Updates combo and back-to-back counters and adds points for the lock
+    fn update_score(&mut self, cleared_lines_count: u32, tspin: TSpin) -> LockResult {
+        let is_difficult = is_difficult_clear(cleared_lines_count, tspin);
+        let is_back_to_back = is_difficult && self.back_to_back;
+        let is_perfect_clear = cleared_lines_count > 0 && self.field.is_empty();
+        let combo = self.combo;
+        let mut score = self.settings.scoring.get_lock_score(cleared_lines_count, tspin, self.level, combo, is_back_to_back);
+        if is_perfect_clear {
+            score += self.settings.scoring.get_perfect_clear_bonus(cleared_lines_count, self.level, is_back_to_back);
+        }
+        self.current_score += score;
 
-        self.current_score += score_base * (self.level + 1) as u64;
+        if cleared_lines_count > 0 {
+            self.combo += 1;
+            self.back_to_back = is_difficult;
+        } else {
+            self.combo = 0;
+        }
+
+        LockResult {
+            block_type: self.current_figure.block_type,
+            cleared_lines_count,
+            tspin,
+            combo,
+            is_back_to_back,
+            is_perfect_clear,
+            score,
+        }
     }
 
     fn update_level_progress(&mut self, cleared_lines_count: u32) {
+        self.lines_cleared += cleared_lines_count;
         self.progress_to_next_level += cleared_lines_count;
 
-        if self.progress_to_next_level >= (self.level * 5) {
-            self.progress_to_next_level -= self.level * 5;
+        let lines_per_level = self.game_mode.get_lines_per_level(self.level);
+        if self.level < self.game_mode.get_max_level() && self.progress_to_next_level >= lines_per_level {
+            self.progress_to_next_level -= lines_per_level;
             self.level += 1;
+            self.apply_level_speed();
+            self.events.push(GameEvent::LevelUp { level: self.level });
         }
     }
-}
\ No newline at end of file
+}
+
+// Field gets wider than the settings say if the piece set has figures that would not fit
+fn create_field(settings: &GameSettings, piece_set: &PieceSet) -> Field {
+    Field::init(settings.get_field_width().max(piece_set.get_widest_piece()), settings.get_total_height())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_settings::MAX_PREVIEW_COUNT;
+    use crate::randomizer::RandomizerType;
+    use crate::game_mode::GameModeType;
+    use crate::tetronimoe::BlockType::*;
+
+    // Current figure and the whole preview
+    fn first_block_types(state: &GameState) -> Vec<BlockType> {
+        let mut block_types = vec![state.current_figure.block_type];
+        block_types.extend(state.peek_next());
+        block_types
+    }
+
+    fn init_game(randomizer: RandomizerType, seed: u64) -> GameState {
+        let settings = GameSettings {
+            randomizer,
+            preview_count: MAX_PREVIEW_COUNT,
+            ..GameSettings::default()
+        };
+        GameState::init(settings, seed)
+    }
+
+    // Tall field, so figures dropped by the tests never reach the top
+    fn init_dig_game(dig_rows: u32, field_height: usize) -> GameState {
+        let settings = GameSettings {
+            game_mode: GameModeType::Dig,
+            dig_rows,
+            field_height,
+            ..GameSettings::default()
+        };
+        GameState::init(settings, 0)
+    }
+
+    // Rows are counted from the bottom of the field in these tests, 0 is the lowest one
+    fn dig_rows_from_bottom(state: &GameState) -> Vec<usize> {
+        let mut rows: Vec<usize> = state.dig_garbage_rows.iter().map(|row| state.field.height - 1 - row).collect();
+        rows.sort_unstable();
+        rows
+    }
+
+    // Fills the given rows and locks the current figure on top of the stack, so the rows are cleared
+    fn clear_rows_from_bottom(state: &mut GameState, rows: &[usize]) {
+        let mut field = state.field.clone();
+        for row in rows {
+            field.rows[field.height - 1 - row] = field.get_full_row();
+        }
+        state.store_field(field);
+        state.hard_drop();
+    }
+
+    #[test]
+    fn seed_gives_the_same_figures() {
+        // Shared seeds and saved replays depend on these, they must never change
+        let mut state = init_game(RandomizerType::SevenBag, 7);
+        let expected = vec![Squiggle, LinePiece, TBlock, Square, ReverseSquiggle, LBlock, ReverseLBlock];
+        assert_eq!(first_block_types(&state), expected);
+        assert_eq!(first_block_types(&init_game(RandomizerType::PureRandom, 7)),
+            vec![Squiggle, Squiggle, TBlock, ReverseLBlock, ReverseSquiggle, Square, ReverseSquiggle]);
+
+        state.hard_drop();
+        state.hard_drop();
+        state.restart_with_seed(7);
+        assert_eq!(first_block_types(&state), expected);
+    }
+
+    #[test]
+    fn dig_rows_are_refilled() {
+        let mut state = init_dig_game(18, 40);
+        assert_eq!(dig_rows_from_bottom(&state), (0..10).collect::<Vec<usize>>());
+        assert!(state.field.rows[state.field.height - 10..].iter().all(|row| row.count_ones() == 9));
+        assert_eq!(state.garbage_rows_left, 18);
+
+        clear_rows_from_bottom(&mut state, &[0, 3]);
+        assert_eq!(state.garbage_rows_left, 16);
+        assert_eq!(dig_rows_from_bottom(&state), (0..10).collect::<Vec<usize>>());
+        assert!(state.field.rows[state.field.height - 10..].iter().all(|row| row.count_ones() == 9));
+
+        // small fields show garbage on half of the visible rows
+        assert_eq!(dig_rows_from_bottom(&init_dig_game(18, 6)), vec![0, 1, 2]);
+        assert_eq!(dig_rows_from_bottom(&init_dig_game(2, 40)), vec![0, 1]);
+    }
+
+    #[test]
+    fn received_garbage_is_not_dug() {
+        // 10 dig rows on the field, 2 more to come
+        let mut state = init_dig_game(12, 40);
+        state.receive_garbage(2, 0);
+        state.raise_pending_garbage();
+        assert_eq!(dig_rows_from_bottom(&state), (2..12).collect::<Vec<usize>>());
+
+        // the new dig row comes under the received ones
+        clear_rows_from_bottom(&mut state, &[2]);
+        assert_eq!(state.garbage_rows_left, 11);
+        assert_eq!(dig_rows_from_bottom(&state), [0].iter().copied().chain(3..12).collect::<Vec<usize>>());
+
+        clear_rows_from_bottom(&mut state, &[1, 2]);
+        assert_eq!(state.garbage_rows_left, 11);
+        assert_eq!(dig_rows_from_bottom(&state), (0..10).collect::<Vec<usize>>());
+
+        clear_rows_from_bottom(&mut state, &(0..10).collect::<Vec<usize>>());
+        assert_eq!(state.garbage_rows_left, 1);
+        assert_eq!(dig_rows_from_bottom(&state), vec![0]);
+        assert_eq!(state.game_over_reason, None);
+
+        clear_rows_from_bottom(&mut state, &[0]);
+        assert_eq!(state.garbage_rows_left, 0);
+        assert_eq!(state.game_over_reason, Some(GameOverReason::Completed));
+    }
+
+    fn count_game_overs(events: &[GameEvent]) -> usize {
+        events.iter().filter(|event| matches!(event, GameEvent::GameOver {..})).count()
+    }
+
+    #[test]
+    fn line_clear_holds_garbage_back() {
+        let mut state = GameState::init(GameSettings::default(), 0);
+        state.receive_garbage(2, 3);
+        state.receive_garbage(1, 100);
+        assert_eq!(state.get_pending_garbage_rows(), 3);
+
+        clear_rows_from_bottom(&mut state, &[0]);
+        assert_eq!(state.get_pending_garbage_rows(), 3);
+        assert!(!state.drain_events().contains(&GameEvent::GarbageAdded { rows: 3 }));
+
+        state.hard_drop();
+        assert_eq!(state.get_pending_garbage_rows(), 0);
+        assert!(state.drain_events().contains(&GameEvent::GarbageAdded { rows: 3 }));
+        // the first received row comes up first, hole outside of the field goes to the last column
+        let full_row = state.field.get_full_row();
+        assert_eq!(state.field.rows[state.field.height - 3..], [full_row & !(1 << 3), full_row & !(1 << 3), full_row & !(1 << 9)]);
+    }
+
+    #[test]
+    fn garbage_pushes_figure_up() {
+        let mut state = GameState::init(GameSettings::default(), 0);
+        let spawn_position = state.current_figure.center;
+        state.receive_garbage(3, 0);
+        state.raise_pending_garbage();
+        assert_eq!(state.current_figure.center.y, spawn_position.y);
+
+        state.current_figure = state.get_landing_position();
+        let landing_row = state.current_figure.center.y;
+        state.receive_garbage(3, 0);
+        state.raise_pending_garbage();
+        assert_eq!(state.current_figure.center.y, landing_row - 3);
+        assert!(position_is_clear(&state.current_figure, &state.field));
+        assert_eq!(state.game_over_reason, None);
+    }
+
+    #[test]
+    fn garbage_out() {
+        // blocks of the stack are pushed out of the top row
+        let mut state = GameState::init(GameSettings::default(), 0);
+        let mut field = state.field.clone();
+        field.set_filled(0, 0, true);
+        state.store_field(field);
+        state.receive_garbage(2, 0);
+        state.raise_pending_garbage();
+        assert_eq!(state.game_over_reason, Some(GameOverReason::GarbageOut));
+        assert_eq!(count_game_overs(&state.drain_events()), 1);
+
+        // stack fits, but the figure on top of it does not
+        let mut state = GameState::init(GameSettings::default(), 0);
+        let mut field = state.field.clone();
+        let buffer_height = state.settings.get_buffer_height();
+        let garbage_row = field.get_full_row() & !1;
+        for row in field.rows[buffer_height..].iter_mut() {
+            *row = garbage_row;
+        }
+        state.store_field(field);
+        state.receive_garbage(buffer_height as u32, 0);
+        state.raise_pending_garbage();
+        assert!(state.field.rows.iter().all(|row| *row != 0));
+        assert_eq!(state.game_over_reason, Some(GameOverReason::GarbageOut));
+        assert_eq!(count_game_overs(&state.drain_events()), 1);
+    }
+}
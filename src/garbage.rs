@@ -0,0 +1,40 @@
+use rand_chacha::ChaCha8Rng;
+use rand::{Rng, SeedableRng};
+
+// Rows sent by someone else, they wait in GameState::pending_garbage until they come up
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PendingGarbage {
+    pub rows: u32,
+    pub hole_column: usize, // the same for all rows
+}
+
+// Picks hole columns for garbage rows.
+// Messiness is the chance in percent that the hole moves to another column on the next row:
+// 0 makes one straight well, 100 puts every hole into a new random column.
+pub struct GarbageGenerator {
+    rng: ChaCha8Rng, // separate from figures, so garbage does not change their sequence
+    messiness: u32,
+    hole_column: Option<usize>,
+}
+
+impl GarbageGenerator {
+    pub fn init(seed: u64, messiness: u32) -> GarbageGenerator {
+        GarbageGenerator {
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            messiness,
+            hole_column: None,
+        }
+    }
+
+    pub fn next_hole_column(&mut self, field_width: usize) -> usize {
+        let hole_column = match self.hole_column {
+            Some(column) if column < field_width && self.rng.gen_range(0, 100) >= self.messiness => column,
+            // any column except the previous one
+            Some(column) if field_width > 1 => (column + self.rng.gen_range(1, field_width)) % field_width,
+            _ => self.rng.gen_range(0, field_width),
+        };
+
+        self.hole_column = Some(hole_column);
+        hole_column
+    }
+}
@@ -0,0 +1,249 @@
+use crate::game_state::GameState;
+use crate::tetronimoe::Direction;
+
+// Everything player can do, independent from the actual keys
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    SoftDrop,
+    HardDrop,
+    RotateClockwise,
+    RotateCounterClockwise,
+    Hold,
+    Pause,
+}
+
+// All durations are in frames (engine ticks)
+#[derive(Copy, Clone, Debug)]
+pub struct InputSettings {
+    pub das: u32, // Delayed Auto Shift: how long to hold left/right before figure starts moving by itself
+    pub arr: u32, // Auto Repeat Rate: frames between moves after DAS, 0 moves figure straight to the wall
+    pub das_cut: u32, // auto shift is paused for this time after rotation, so rotated figure doesn't fly away
+    pub soft_drop_factor: f64, // how many times soft drop is faster than normal gravity
+}
+
+impl Default for InputSettings {
+    fn default() -> InputSettings {
+        InputSettings {
+            das: 10,
+            arr: 2,
+            das_cut: 0,
+            soft_drop_factor: 20.0,
+        }
+    }
+}
+
+// Tracks held keys and turns them into game actions.
+// Key repeat of the OS is ignored, holding is handled by DAS/ARR.
+pub struct InputHandler {
+    pub settings: InputSettings,
+    held_actions: Vec<Action>, // in order of pressing, the last one is the newest
+    das_frames: u32, // how long current direction is held
+    arr_frames: u32, // frames since the last auto shift, starts charged so the first shift happens right after DAS
+    das_cut_frames_left: u32,
+}
+
+impl InputHandler {
+    pub fn init(settings: InputSettings) -> InputHandler {
+        InputHandler {
+            settings,
+            held_actions: vec![],
+            das_frames: 0,
+            arr_frames: settings.arr,
+            das_cut_frames_left: 0,
+        }
+    }
+
+    pub fn press(&mut self, action: Action, game_state: &mut GameState) {
+        if self.held_actions.contains(&action) {
+            return;
+        }
+        self.held_actions.push(action);
+
+        if game_state.is_paused && action != Action::Pause {
+            return;
+        }
+
+        match action {
+            Action::MoveLeft => {
+                self.restart_auto_shift();
+                game_state.left_shift();
+            },
+            Action::MoveRight => {
+                self.restart_auto_shift();
+                game_state.right_shift();
+            },
+            Action::SoftDrop => game_state.set_soft_drop_gravity(self.settings.soft_drop_factor),
+            Action::HardDrop => game_state.hard_drop(),
+            Action::RotateClockwise => {
+                game_state.rotate_clockwise();
+                self.das_cut_frames_left = self.settings.das_cut;
+            },
+            Action::RotateCounterClockwise => {
+                game_state.rotate_counter_clockwise();
+                self.das_cut_frames_left = self.settings.das_cut;
+            },
+            Action::Hold => game_state.hold(),
+            Action::Pause => game_state.pause(),
+        }
+    }
+
+    pub fn release(&mut self, action: Action, game_state: &mut GameState) {
+        let was_active_direction = self.get_active_direction() == get_action_direction(action);
+        self.held_actions.retain(|held_action| *held_action != action);
+
+        match action {
+            // if both directions were held, the other one takes over with fresh DAS
+            Action::MoveLeft | Action::MoveRight if was_active_direction => self.restart_auto_shift(),
+            Action::SoftDrop => game_state.reset_gravity(),
+            _ => (),
+        }
+    }
+
+    // For cases when key releases can't be tracked, e.g. window lost focus
+    pub fn release_all(&mut self, game_state: &mut GameState) {
+        for action in self.held_actions.clone() {
+            self.release(action, game_state);
+        }
+    }
+
+    // Must be called once per tick, before GameState::update
+    pub fn update(&mut self, game_state: &mut GameState) {
+        if game_state.is_paused {
+            return;
+        }
+
+        let direction = match self.get_active_direction() {
+            Some(direction) => direction,
+            None => return,
+        };
+
+        self.das_frames = self.das_frames.saturating_add(1);
+        if self.das_cut_frames_left > 0 {
+            self.das_cut_frames_left -= 1;
+            return;
+        }
+
+        if self.das_frames < self.settings.das {
+            return;
+        }
+
+        if self.settings.arr == 0 {
+            while shift(direction, game_state) {}
+            return;
+        }
+
+        self.arr_frames += 1;
+        if self.arr_frames >= self.settings.arr {
+            self.arr_frames = 0;
+            shift(direction, game_state);
+        }
+    }
+
+    fn get_active_direction(&self) -> Option<Direction> {
+        self.held_actions.iter().rev().find_map(|action| get_action_direction(*action))
+    }
+
+    fn restart_auto_shift(&mut self) {
+        self.das_frames = 0;
+        self.arr_frames = self.settings.arr;
+    }
+}
+
+fn get_action_direction(action: Action) -> Option<Direction> {
+    match action {
+        Action::MoveLeft => Some(Direction::Left),
+        Action::MoveRight => Some(Direction::Right),
+        _ => None,
+    }
+}
+
+fn shift(direction: Direction, game_state: &mut GameState) -> bool {
+    match direction {
+        Direction::Left => game_state.left_shift(),
+        Direction::Right => game_state.right_shift(),
+        _ => unreachable!(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_settings::GameSettings;
+    use crate::tetronimoe::{BlockType, Figure, Point};
+
+    // T in the middle of an empty field, it can go from x = 1 to x = 8
+    fn init_game() -> GameState {
+        let mut state = GameState::init(GameSettings::default(), 0);
+        state.current_figure = Figure::init(BlockType::TBlock, Direction::Up, Point {x: 4, y: 19}, state.rotation_system.as_ref());
+        state
+    }
+
+    fn init_input(das: u32, arr: u32, das_cut: u32) -> InputHandler {
+        InputHandler::init(InputSettings { das, arr, das_cut, ..InputSettings::default() })
+    }
+
+    // Center column of the figure after each tick
+    fn record_x(input_handler: &mut InputHandler, state: &mut GameState, ticks: usize) -> Vec<isize> {
+        (0..ticks).map(|_| {
+            input_handler.update(state);
+            state.update();
+            state.current_figure.center.x
+        }).collect()
+    }
+
+    #[test]
+    fn das_and_arr() {
+        let mut state = init_game();
+        let mut input_handler = init_input(3, 2, 0);
+        input_handler.press(Action::MoveRight, &mut state);
+        assert_eq!(state.current_figure.center.x, 5);
+        assert_eq!(record_x(&mut input_handler, &mut state, 8), vec![5, 5, 6, 6, 7, 7, 8, 8]);
+
+        // released key stops the figure, the next press starts DAS again
+        input_handler.release(Action::MoveRight, &mut state);
+        input_handler.press(Action::MoveLeft, &mut state);
+        assert_eq!(record_x(&mut input_handler, &mut state, 4), vec![7, 7, 6, 6]);
+    }
+
+    #[test]
+    fn instant_arr() {
+        let mut state = init_game();
+        let mut input_handler = init_input(3, 0, 0);
+        input_handler.press(Action::MoveRight, &mut state);
+        assert_eq!(record_x(&mut input_handler, &mut state, 4), vec![5, 5, 8, 8]);
+    }
+
+    #[test]
+    fn das_cut() {
+        let mut state = init_game();
+        let mut input_handler = init_input(3, 1, 2);
+        input_handler.press(Action::MoveRight, &mut state);
+        assert_eq!(record_x(&mut input_handler, &mut state, 2), vec![5, 5]);
+        input_handler.press(Action::RotateClockwise, &mut state);
+        assert_eq!(record_x(&mut input_handler, &mut state, 4), vec![5, 5, 6, 7]);
+    }
+
+    #[test]
+    fn release_hand_off() {
+        let mut state = init_game();
+        let mut input_handler = init_input(3, 1, 0);
+        input_handler.press(Action::MoveRight, &mut state);
+        assert_eq!(record_x(&mut input_handler, &mut state, 3), vec![5, 5, 6]);
+
+        // the newest direction wins
+        input_handler.press(Action::MoveLeft, &mut state);
+        assert_eq!(record_x(&mut input_handler, &mut state, 3), vec![5, 5, 4]);
+
+        // the other held direction takes over with fresh DAS
+        input_handler.release(Action::MoveLeft, &mut state);
+        assert_eq!(record_x(&mut input_handler, &mut state, 3), vec![4, 4, 5]);
+
+        // releasing the older direction does not restart DAS of the newer one
+        input_handler.press(Action::MoveLeft, &mut state);
+        assert_eq!(record_x(&mut input_handler, &mut state, 2), vec![4, 4]);
+        input_handler.release(Action::MoveRight, &mut state);
+        assert_eq!(record_x(&mut input_handler, &mut state, 2), vec![3, 2]);
+    }
+}
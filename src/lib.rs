@@ -0,0 +1,23 @@
+// Game engine. Knows nothing about windows, OpenGL or fonts,
+// so it can be used by bots, tests and servers without a display.
+// Rendering and input handling live in the binary behind the `gui` feature.
+
+pub mod tetronimoe;
+pub mod game_state;
+pub mod collision_checker;
+pub mod field;
+pub mod rotation_system;
+pub mod game_settings;
+pub mod randomizer;
+pub mod save_data;
+pub mod lock_delay;
+pub mod input_handler;
+pub mod tspin;
+pub mod scoring;
+pub mod events;
+pub mod timestep;
+pub mod speed_curve;
+pub mod game_mode;
+pub mod garbage;
+pub mod piece_set;
+mod text_file;
@@ -0,0 +1,157 @@
+// Number of moves and rotations that reset lock delay in MoveReset policy
+pub const MOVE_RESET_LIMIT: u32 = 15;
+
+// What gives the figure more time on the ground before locking.
+// Falling to a row lower than ever before always resets the timer.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum LockResetPolicy {
+    StepReset, // nothing else, classic TGM behaviour
+    MoveReset, // every move or rotation on the ground, but no more than MOVE_RESET_LIMIT times
+    Infinite, // every move or rotation on the ground, without limits
+}
+
+// Counts frames figure spends on the ground
+pub struct LockDelay {
+    pub policy: LockResetPolicy,
+    pub delay: u32, // frames figure stays on the ground without locking, it locks on the frame after them
+    pub frames_left: u32,
+    pub moves_left: u32,
+    lowest_row: isize,
+}
+
+impl LockDelay {
+    pub fn init(delay: u32, policy: LockResetPolicy) -> LockDelay {
+        LockDelay {
+            policy,
+            delay,
+            frames_left: delay,
+            moves_left: MOVE_RESET_LIMIT,
+            lowest_row: isize::MIN,
+        }
+    }
+
+    // New figure gets full delay
+    pub fn reset(&mut self, row: isize) {
+        self.frames_left = self.delay;
+        self.moves_left = MOVE_RESET_LIMIT;
+        self.lowest_row = row;
+    }
+
+    // Figure fell down, row is its new center row
+    pub fn on_step_down(&mut self, row: isize) {
+        if row > self.lowest_row {
+            self.reset(row);
+        }
+    }
+
+    // Figure was moved or rotated successfully.
+    // Moves count only after the timer is started, i.e. figure has touched the ground.
+    pub fn on_move(&mut self) {
+        match self.policy {
+            LockResetPolicy::StepReset => (),
+            LockResetPolicy::MoveReset => {
+                if self.frames_left < self.delay && self.moves_left > 0 {
+                    self.moves_left -= 1;
+                    self.frames_left = self.delay;
+                }
+            },
+            LockResetPolicy::Infinite => self.frames_left = self.delay,
+        }
+    }
+
+    // Called every frame while figure is on the ground, returns true if figure must be locked.
+    // With delay 30 that is the 31st call after a reset, with delay 0 the first one.
+    pub fn tick(&mut self) -> bool {
+        if self.frames_left == 0 {
+            return true;
+        }
+
+        self.frames_left -= 1;
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Frames on the ground until the figure locks, the locking one included
+    fn frames_until_lock(lock_delay: &mut LockDelay) -> u32 {
+        let mut frames = 1;
+        while !lock_delay.tick() {
+            frames += 1;
+        }
+        frames
+    }
+
+    #[test]
+    fn locks_after_delay() {
+        let mut lock_delay = LockDelay::init(30, LockResetPolicy::MoveReset);
+        lock_delay.reset(10);
+        assert_eq!(frames_until_lock(&mut lock_delay), 31);
+
+        // old games lock on touch
+        let mut lock_delay = LockDelay::init(0, LockResetPolicy::StepReset);
+        lock_delay.reset(10);
+        assert_eq!(frames_until_lock(&mut lock_delay), 1);
+    }
+
+    #[test]
+    fn step_reset() {
+        let mut lock_delay = LockDelay::init(3, LockResetPolicy::StepReset);
+        lock_delay.reset(10);
+        lock_delay.tick();
+        lock_delay.on_move();
+        assert_eq!(lock_delay.frames_left, 2);
+
+        // only a row lower than ever before resets the timer
+        lock_delay.on_step_down(10);
+        lock_delay.on_step_down(9);
+        assert_eq!(lock_delay.frames_left, 2);
+        lock_delay.on_step_down(11);
+        assert_eq!(lock_delay.frames_left, 3);
+        lock_delay.tick();
+        lock_delay.on_step_down(11);
+        assert_eq!(frames_until_lock(&mut lock_delay), 3);
+    }
+
+    #[test]
+    fn move_reset() {
+        let mut lock_delay = LockDelay::init(3, LockResetPolicy::MoveReset);
+        lock_delay.reset(10);
+
+        // moves in the air are free
+        lock_delay.on_move();
+        assert_eq!(lock_delay.moves_left, MOVE_RESET_LIMIT);
+
+        for moves_left in (0..MOVE_RESET_LIMIT).rev() {
+            lock_delay.tick();
+            lock_delay.on_move();
+            assert_eq!(lock_delay.frames_left, 3);
+            assert_eq!(lock_delay.moves_left, moves_left);
+        }
+
+        lock_delay.tick();
+        lock_delay.on_move();
+        assert_eq!(lock_delay.frames_left, 2);
+        assert_eq!(frames_until_lock(&mut lock_delay), 3);
+
+        // new lowest row gives all moves back
+        lock_delay.on_step_down(11);
+        assert_eq!(lock_delay.moves_left, MOVE_RESET_LIMIT);
+        assert_eq!(lock_delay.frames_left, 3);
+    }
+
+    #[test]
+    fn infinite() {
+        let mut lock_delay = LockDelay::init(3, LockResetPolicy::Infinite);
+        lock_delay.reset(10);
+        for _ in 0..MOVE_RESET_LIMIT * 2 {
+            lock_delay.tick();
+            lock_delay.tick();
+            lock_delay.on_move();
+            assert_eq!(lock_delay.frames_left, 3);
+        }
+        assert_eq!(frames_until_lock(&mut lock_delay), 4);
+    }
+}
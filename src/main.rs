@@ -1,12 +1,8 @@
 #![windows_subsystem = "windows"]
 
-mod tetronimoe;
-mod game_state;
 mod state_renderer;
-mod collision_checker;
 mod texture_bag;
 mod shader_program;
-mod normal;
 mod vertex;
 
 #[macro_use]
@@ -16,98 +12,187 @@ use winit::event_loop::ControlFlow;
 use glium::{glutin, Surface};
 use std::time::Instant;
 use std::fs::File;
+use std::path::Path;
 use winit::event::{Event, WindowEvent, ElementState, VirtualKeyCode, DeviceEvent};
-use crate::game_state::GameState;
-use crate::state_renderer::render_sate;
+use rustris::game_state::GameState;
+use rustris::game_settings::{GameSettings, MAX_PREVIEW_COUNT};
+use rustris::save_data::SaveData;
+use rustris::timestep::FixedTimestep;
+use rustris::speed_curve::SpeedCurve;
+use rustris::piece_set::PieceSet;
+use rustris::events::GameEvent;
+use rustris::input_handler::{InputHandler, InputSettings, Action};
+use crate::state_renderer::{render_sate, Banners};
 use std::borrow::Borrow;
-use crate::vertex::Vertex;
-use crate::normal::Normal;
 use crate::texture_bag::TextureBag;
-use crate::collision_checker::position_is_clear;
 
-fn main() {
-    implement_vertex!(Vertex, position, texture);
-    implement_vertex!(Normal, normal);
+const SAVE_FILE_PATH: &str = "rustris.save";
+// Optional custom speed curve, see SpeedCurve::load for the format
+const SPEED_CURVE_PATH: &str = "rustris.speed";
+// Optional custom piece set, see PieceSet::load for the format
+const PIECE_SET_PATH: &str = "rustris.pieces";
 
+fn main() {
     let event_loop = glutin::event_loop::EventLoop::new();
     let window_builder = glutin::window::WindowBuilder::new().with_title("RTetris");
     let context = glutin::ContextBuilder::new().with_depth_buffer(24);
     let display = glium::Display::new(window_builder, context, &event_loop).unwrap();
     let textures = TextureBag::init(&display);
 
-    let mut frame_counter: u32 = 0;
-    let mut frame_start = Instant::now();
-    let mut frame_duration = Instant::now().duration_since(frame_start).as_millis() as u64;
-
     let font = glium_text_nxt::FontTexture::new(&display, File::open("ClearSans-Medium.ttf").unwrap(), 14).unwrap();
     let mut in_focus = true;
 
-    let mut game_state = GameState::init();
+    let mut save_data = SaveData::load(SAVE_FILE_PATH);
+    let mut game_state = GameState::init(GameSettings::default(), rand::random::<u32>() as u64);
+    game_state.best_score = save_data.best_score;
+    if Path::new(SPEED_CURVE_PATH).exists() {
+        match SpeedCurve::load(SPEED_CURVE_PATH) {
+            Ok(speed_curve) => game_state.set_speed_curve(speed_curve),
+            Err(error) => eprintln!("Can't load speed curve: {}", error),
+        }
+    }
+    if Path::new(PIECE_SET_PATH).exists() {
+        match PieceSet::load(PIECE_SET_PATH) {
+            Ok(piece_set) => game_state.set_piece_set(piece_set),
+            Err(error) => eprintln!("Can't load piece set: {}", error),
+        }
+    }
+    let mut input_handler = InputHandler::init(InputSettings::default());
+    let mut banners = Banners::init();
+    let mut timestep = FixedTimestep::init();
+    let mut last_update = Instant::now();
 
     event_loop.run(move |event, _, control_flow| {
         *control_flow = ControlFlow::Poll;
-        if frame_duration >= 16 {
-            frame_counter += 1;
+        // Game runs in fixed ticks whatever the frame rate is,
+        // picture is redrawn only when something could change
+        let now = Instant::now();
+        let ticks = timestep.advance(now.duration_since(last_update));
+        last_update = now;
+        for _ in 0..ticks {
+            input_handler.update(&mut game_state);
+            game_state.update();
+            banners.update();
+            for event in game_state.drain_events() {
+                banners.handle_event(&event);
+                // result stays on the screen until a new game is started
+                if let GameEvent::GameOver {..} = event {
+                    save_data.best_score = game_state.best_score;
+                    save_data.last_seed = Some(game_state.seed);
+                    save_data.last_randomizer = Some(game_state.settings.randomizer);
+                    save_data.last_piece_set = Some(game_state.settings.piece_set);
+                    save_data.last_rotation_system = Some(game_state.settings.rotation_system);
+                    if let Err(error) = save_data.store(SAVE_FILE_PATH) {
+                        eprintln!("Can't save game data: {}", error);
+                    }
+                }
+            }
+        }
 
+        if ticks > 0 {
             let mut target = display.draw();
             target.clear_color_and_depth((0.0, 0.0, 1.0, 1.0), 1.0);
-            render_sate(&game_state, &display, &mut target, font.borrow(), &textures);
+            render_sate(&game_state, &banners, &display, &mut target, font.borrow(), &textures);
             target.finish().unwrap();
-            frame_start = Instant::now();
-
-            game_state.update();
-            if game_state.game_is_finished() {
-                game_state.restart();
-            }
         }
 
         match event {
-            Event::LoopDestroyed => return,
+            Event::LoopDestroyed => (),
             Event::WindowEvent {event, ..} => {
                 match event {
                     WindowEvent::Resized(size) => display.gl_window().resize(size),
                     WindowEvent::CloseRequested => *control_flow = ControlFlow::Exit,
-                    WindowEvent::Focused(focused) => in_focus = focused,
+                    WindowEvent::Focused(focused) => {
+                        in_focus = focused;
+                        if !focused {
+                            input_handler.release_all(&mut game_state);
+                        }
+                    },
                     _ => (),
                 }
             },
-            Event::DeviceEvent {event, ..} => {
-                match event {
-                    DeviceEvent::Key(input) => {
-                        if in_focus && input.state == ElementState::Pressed {
-                            match input.virtual_keycode.unwrap() {
-                                VirtualKeyCode::Numpad1
-                                | VirtualKeyCode::Numpad5
-                                | VirtualKeyCode::Numpad9 => game_state.rotate_clockwise(),
-                                VirtualKeyCode::Numpad3
-                                | VirtualKeyCode::Numpad7 => game_state.rotate_counter_clockwise(),
-                                VirtualKeyCode::Numpad8 => game_state.set_hard_drop_gravity(),
-                                VirtualKeyCode::Numpad2 => game_state.set_soft_drop_gravity(),
-                                VirtualKeyCode::Numpad4 => game_state.left_shift(),
-                                VirtualKeyCode::Numpad6 => game_state.right_shift(),
-                                VirtualKeyCode::Numpad0 => game_state.hold(),
-                                VirtualKeyCode::Escape | VirtualKeyCode::F1 => game_state.pause(),
-                                _ => (),
-                            }
-                        }
-
-                        if in_focus && input.state == ElementState::Released {
-                            match input.virtual_keycode.unwrap() {
-                                VirtualKeyCode::Numpad8 => game_state.reset_gravity(),
-                                VirtualKeyCode::Numpad2 => game_state.reset_gravity(),
-                                _ => (),
-                            }
-                        }
+            Event::DeviceEvent {event: DeviceEvent::Key(input), ..} if in_focus => {
+                if let Some(key) = input.virtual_keycode {
+                    match (get_key_action(key), input.state) {
+                        (Some(action), ElementState::Pressed) => input_handler.press(action, &mut game_state),
+                        (Some(action), ElementState::Released) => input_handler.release(action, &mut game_state),
+                        (None, ElementState::Pressed) => handle_settings_key(key, &mut game_state, &save_data),
+                        (None, ElementState::Released) => (),
                     }
-                    _ => (),
                 }
             },
             Event::RedrawRequested(_) => {
-                display.swap_buffers();
+                display.swap_buffers().unwrap();
             },
             _ => (),
         }
-
-        frame_duration = Instant::now().duration_since(frame_start).as_millis() as u64;
     });
 }
+
+
+fn get_key_action(key: VirtualKeyCode) -> Option<Action> {
+    match key {
+        VirtualKeyCode::Numpad1
+        | VirtualKeyCode::Numpad5
+        | VirtualKeyCode::Numpad9 => Some(Action::RotateClockwise),
+        VirtualKeyCode::Numpad3
+        | VirtualKeyCode::Numpad7 => Some(Action::RotateCounterClockwise),
+        VirtualKeyCode::Numpad8 => Some(Action::HardDrop),
+        VirtualKeyCode::Numpad2 => Some(Action::SoftDrop),
+        VirtualKeyCode::Numpad4 => Some(Action::MoveLeft),
+        VirtualKeyCode::Numpad6 => Some(Action::MoveRight),
+        VirtualKeyCode::Numpad0 => Some(Action::Hold),
+        VirtualKeyCode::Escape | VirtualKeyCode::F1 => Some(Action::Pause),
+        _ => None,
+    }
+}
+
+// Keys that change the game itself, not the figure
+fn handle_settings_key(key: VirtualKeyCode, game_state: &mut GameState, save_data: &SaveData) {
+    match key {
+        VirtualKeyCode::F2 => {
+            let mut settings = game_state.settings;
+            settings.rotation_system = settings.rotation_system.next();
+            game_state.restart_with_settings(settings);
+        },
+        VirtualKeyCode::F3 => {
+            let mut settings = game_state.settings;
+            settings.randomizer = settings.randomizer.next();
+            game_state.restart_with_settings(settings);
+        },
+        VirtualKeyCode::F4 => {
+            // same seed gives the same figures only with the same rules
+            if let Some(seed) = save_data.last_seed {
+                let mut settings = game_state.settings;
+                settings.randomizer = save_data.last_randomizer.unwrap_or(settings.randomizer);
+                settings.piece_set = save_data.last_piece_set.unwrap_or(settings.piece_set);
+                settings.rotation_system = save_data.last_rotation_system.unwrap_or(settings.rotation_system);
+                game_state.restart_with_settings(settings);
+                game_state.restart_with_seed(seed);
+            }
+        },
+        VirtualKeyCode::F5 => {
+            let mut settings = game_state.settings;
+            settings.preview_count = settings.get_preview_count() % MAX_PREVIEW_COUNT + 1;
+            game_state.restart_with_settings(settings);
+        },
+        VirtualKeyCode::F6 => game_state.settings.show_ghost = !game_state.settings.show_ghost,
+        VirtualKeyCode::F7 => {
+            let mut settings = game_state.settings;
+            settings.speed_curve = settings.speed_curve.next();
+            game_state.restart_with_settings(settings);
+        },
+        VirtualKeyCode::F8 => {
+            let mut settings = game_state.settings;
+            settings.game_mode = settings.game_mode.next();
+            game_state.restart_with_settings(settings);
+        },
+        VirtualKeyCode::F9 => {
+            let mut settings = game_state.settings;
+            settings.piece_set = settings.piece_set.next();
+            game_state.restart_with_settings(settings);
+        },
+        VirtualKeyCode::Return => game_state.restart(),
+        _ => (),
+    }
+}
@@ -1,4 +0,0 @@
-#[derive(Copy, Clone)]
-pub struct Normal {
-    pub normal: [f32; 3],
-}
\ No newline at end of file
@@ -0,0 +1,396 @@
+use std::fs;
+use std::io;
+use crate::tetronimoe::{BlockType, Direction, Point, BLOCK_TYPES};
+use crate::text_file::{parse_value, invalid_data};
+
+// Figure that is not one of the seven standard ones, it is BlockType::Custom(index in PieceSet::shapes)
+#[derive(Clone, Debug, PartialEq)]
+pub struct PieceShape {
+    pub name: String,
+    pub states: Vec<Vec<(isize, isize)>>, // tiles as offsets from the center for Up, Right, Down and Left
+    pub spawn_offset: (isize, isize), // moves the figure from the default spawn position
+}
+
+impl PieceShape {
+    pub fn get_tiles_offsets(&self, direction: Direction) -> Vec<(isize, isize)> {
+        match direction {
+            Direction::Up => self.states[0].clone(),
+            Direction::Right => self.states[1].clone(),
+            Direction::Down => self.states[2].clone(),
+            Direction::Left => self.states[3].clone(),
+            Direction::None => unreachable!(),
+        }
+    }
+
+    // Center goes to the middle of the field (left middle column for even widths),
+    // the lowest tile goes to the lower row of the spawn area.
+    // Figure is moved away from the walls if it does not fit there.
+    pub fn get_start_position(&self, field_width: usize) -> Point {
+        let leftmost_tile = self.states[0].iter().map(|(x, _)| *x).min().unwrap();
+        let rightmost_tile = self.states[0].iter().map(|(x, _)| *x).max().unwrap();
+        let lowest_tile = self.states[0].iter().map(|(_, y)| *y).max().unwrap();
+        let x = ((field_width - 1) / 2) as isize + self.spawn_offset.0;
+        Point {
+            x: x.min(field_width as isize - 1 - rightmost_tile).max(-leftmost_tile),
+            y: 1 - lowest_tile + self.spawn_offset.1,
+        }
+    }
+
+    // Width of the figure in its widest rotation state
+    pub fn get_width(&self) -> usize {
+        self.states.iter().map(|state| {
+            let leftmost_tile = state.iter().map(|(x, _)| *x).min().unwrap();
+            let rightmost_tile = state.iter().map(|(x, _)| *x).max().unwrap();
+            (rightmost_tile - leftmost_tile + 1) as usize
+        }).max().unwrap()
+    }
+}
+
+// Figures the game is played with
+#[derive(Clone, Debug, PartialEq)]
+pub struct PieceSet {
+    pub name: String,
+    pub has_tetrominoes: bool, // seven standard figures, their shapes come from the rotation system
+    pub shapes: Vec<PieceShape>,
+}
+
+impl PieceSet {
+    pub fn get_block_types(&self) -> Vec<BlockType> {
+        let mut block_types = vec![];
+        if self.has_tetrominoes {
+            block_types.extend_from_slice(&BLOCK_TYPES);
+        }
+        block_types.extend((0..self.shapes.len()).map(BlockType::Custom));
+        block_types
+    }
+
+    // Field must be at least that wide, 0 if there are no custom figures
+    pub fn get_widest_piece(&self) -> usize {
+        self.shapes.iter().map(|shape| shape.get_width()).max().unwrap_or(0)
+    }
+
+    // Plain text. Name goes to the "name=..." line, lines starting with # are comments,
+    // "tetrominoes" line adds the seven standard figures.
+    // Every other figure starts with "piece=<name>" and optional "spawn=<x> <y>" offset,
+    // then go its rotation states for Up, Right, Down and Left, separated by empty lines.
+    // State is a grid: X is a tile, O is a tile in the center of rotation,
+    // + is the center without a tile, . is an empty cell. Grid without center rotates around its middle cell.
+    // Missing states are made by rotating the last given one clockwise.
+    pub fn load(path: &str) -> io::Result<PieceSet> {
+        PieceSet::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(content: &str) -> io::Result<PieceSet> {
+        let mut piece_set = PieceSet {
+            name: String::from("Custom"),
+            has_tetrominoes: false,
+            shapes: vec![],
+        };
+        let mut grid: Vec<&str> = vec![];
+
+        for line in content.lines().map(|line| line.trim()) {
+            if line.starts_with('#') {
+                continue;
+            }
+
+            let is_grid_row = !line.is_empty() && line.chars().all(|cell| "XO+.".contains(cell));
+            if is_grid_row {
+                grid.push(line);
+                continue;
+            }
+
+            // anything else ends the grid
+            if !grid.is_empty() {
+                let shape = piece_set.shapes.last_mut()
+                    .ok_or_else(|| invalid_data(format!("state \"{}\" goes before any piece", grid[0])))?;
+                shape.states.push(parse_state(&grid)?);
+                grid.clear();
+            }
+
+            if line.is_empty() {
+                continue;
+            } else if let Some(name) = line.strip_prefix("name=") {
+                piece_set.name = String::from(name.trim());
+            } else if line == "tetrominoes" {
+                piece_set.has_tetrominoes = true;
+            } else if let Some(name) = line.strip_prefix("piece=") {
+                piece_set.shapes.push(PieceShape {
+                    name: String::from(name.trim()),
+                    states: vec![],
+                    spawn_offset: (0, 0),
+                });
+            } else if let Some(offset) = line.strip_prefix("spawn=") {
+                let values: Vec<&str> = offset.split_whitespace().collect();
+                let shape = piece_set.shapes.last_mut()
+                    .ok_or_else(|| invalid_data(format!("\"{}\" goes before any piece", line)))?;
+                if values.len() != 2 {
+                    return Err(invalid_data(format!("expected 2 values, got \"{}\"", line)));
+                }
+                shape.spawn_offset = (parse_value(values[0], line)?, parse_value(values[1], line)?);
+            } else {
+                return Err(invalid_data(format!("can't parse \"{}\"", line)));
+            }
+        }
+
+        if !grid.is_empty() {
+            let shape = piece_set.shapes.last_mut()
+                .ok_or_else(|| invalid_data(format!("state \"{}\" goes before any piece", grid[0])))?;
+            shape.states.push(parse_state(&grid)?);
+        }
+
+        for shape in piece_set.shapes.iter_mut() {
+            if shape.states.is_empty() || shape.states.len() > 4 {
+                return Err(invalid_data(format!("piece {} has {} states, expected 1 to 4", shape.name, shape.states.len())));
+            }
+            while shape.states.len() < 4 {
+                let rotated = shape.states.last().unwrap().iter().map(|(x, y)| (-y, *x)).collect();
+                shape.states.push(rotated);
+            }
+        }
+
+        if piece_set.get_block_types().is_empty() {
+            return Err(invalid_data(String::from("no pieces")));
+        }
+
+        Ok(piece_set)
+    }
+}
+
+// Tile offsets from the center of the grid
+fn parse_state(grid: &[&str]) -> io::Result<Vec<(isize, isize)>> {
+    let mut tiles = vec![];
+    let mut center = None;
+    for (y, row) in grid.iter().enumerate() {
+        for (x, cell) in row.chars().enumerate() {
+            let position = (x as isize, y as isize);
+            if cell == 'O' || cell == '+' {
+                if center.is_some() {
+                    return Err(invalid_data(format!("state \"{}\" has two centers", grid.join(" "))));
+                }
+                center = Some(position);
+            }
+            if cell == 'X' || cell == 'O' {
+                tiles.push(position);
+            }
+        }
+    }
+
+    if tiles.is_empty() {
+        return Err(invalid_data(format!("state \"{}\" has no tiles", grid.join(" "))));
+    }
+
+    let grid_width = grid.iter().map(|row| row.len()).max().unwrap() as isize;
+    let (center_x, center_y) = center.unwrap_or(((grid_width - 1) / 2, (grid.len() as isize - 1) / 2));
+    Ok(tiles.into_iter().map(|(x, y)| (x - center_x, y - center_y)).collect())
+}
+
+// Built in sets are written in the same format as piece set files
+const PENTOMINOES: &str = "
+name=Pentominoes
+piece=F
+.XX
+XO.
+.X.
+
+piece=F'
+XX.
+.OX
+.X.
+
+piece=I
+XXOXX
+
+piece=L
+...X
+XXOX
+
+piece=J
+X...
+XOXX
+
+piece=N
+XX..
+.XOX
+
+piece=N'
+..XX
+XOX.
+
+piece=P
+XX
+OX
+X.
+
+piece=Q
+XX
+XO
+.X
+
+piece=T
+XXX
+.O.
+.X.
+
+piece=U
+X.X
+XOX
+
+piece=V
+X..
+X..
+XOX
+
+piece=W
+X..
+XO.
+.XX
+
+piece=X
+.X.
+XOX
+.X.
+
+piece=Y
+.X..
+XOXX
+
+piece=Y'
+..X.
+XXOX
+
+piece=Z
+XX.
+.O.
+.XX
+
+piece=S
+.XX
+.O.
+XX.
+";
+
+const TETROMINOES_AND_MONOMINO: &str = "
+name=Tetrominoes + monomino
+tetrominoes
+piece=Monomino
+O
+";
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PieceSetType {
+    Tetrominoes, // the usual seven figures
+    Pentominoes, // all 18 one-sided pentominoes
+    TetrominoesAndMonomino, // party mode: seven figures and a single block
+}
+
+impl PieceSetType {
+    pub fn create(&self) -> PieceSet {
+        match self {
+            PieceSetType::Tetrominoes => PieceSet {
+                name: String::from("Tetrominoes"),
+                has_tetrominoes: true,
+                shapes: vec![],
+            },
+            PieceSetType::Pentominoes => PieceSet::parse(PENTOMINOES).unwrap(),
+            PieceSetType::TetrominoesAndMonomino => PieceSet::parse(TETROMINOES_AND_MONOMINO).unwrap(),
+        }
+    }
+
+    pub fn next(&self) -> PieceSetType {
+        match self {
+            PieceSetType::Tetrominoes => PieceSetType::Pentominoes,
+            PieceSetType::Pentominoes => PieceSetType::TetrominoesAndMonomino,
+            PieceSetType::TetrominoesAndMonomino => PieceSetType::Tetrominoes,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_error(content: &str) -> String {
+        PieceSet::parse(content).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn missing_states_are_rotated_clockwise() {
+        let piece_set = PieceSet::parse("piece=Domino\nOX").unwrap();
+        let states = &piece_set.shapes[0].states;
+        assert_eq!(states[0], vec![(0, 0), (1, 0)]);
+        assert_eq!(states[1], vec![(0, 0), (0, 1)]);
+        assert_eq!(states[2], vec![(0, 0), (-1, 0)]);
+        assert_eq!(states[3], vec![(0, 0), (0, -1)]);
+    }
+
+    #[test]
+    fn given_states_are_kept() {
+        let piece_set = PieceSet::parse("piece=Domino\nOX\n\nO\nX\n\n# comment\nXO").unwrap();
+        let states = &piece_set.shapes[0].states;
+        assert_eq!(states[1], vec![(0, 0), (0, 1)]);
+        assert_eq!(states[2], vec![(-1, 0), (0, 0)]);
+        // the last one is made from the third state
+        assert_eq!(states[3], vec![(0, -1), (0, 0)]);
+    }
+
+    #[test]
+    fn centers() {
+        let with_tile = PieceSet::parse("piece=A\nXO").unwrap();
+        assert_eq!(with_tile.shapes[0].states[0], vec![(-1, 0), (0, 0)]);
+
+        let without_tile = PieceSet::parse("piece=A\nX.\n.+").unwrap();
+        assert_eq!(without_tile.shapes[0].states[0], vec![(-1, -1)]);
+
+        // no center: the middle cell, left and upper one for even sizes
+        let middle = PieceSet::parse("piece=A\nXXX\n..X").unwrap();
+        assert_eq!(middle.shapes[0].states[0], vec![(-1, 0), (0, 0), (1, 0), (1, 1)]);
+    }
+
+    #[test]
+    fn names_spawn_and_tetrominoes() {
+        let piece_set = PieceSet::parse("name=Party\ntetrominoes\npiece=Dot\nspawn=2 -1\nO").unwrap();
+        assert_eq!(piece_set.name, "Party");
+        assert_eq!(piece_set.shapes[0].name, "Dot");
+        assert_eq!(piece_set.shapes[0].spawn_offset, (2, -1));
+        assert_eq!(piece_set.get_block_types().len(), 8);
+        assert_eq!(piece_set.get_block_types()[7], BlockType::Custom(0));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(parse_error("XX"), "state \"XX\" goes before any piece");
+        assert_eq!(parse_error("spawn=0 0"), "\"spawn=0 0\" goes before any piece");
+        assert_eq!(parse_error("piece=A\nXZ"), "can't parse \"XZ\"");
+        assert_eq!(parse_error("piece=A\nspawn=1\nX"), "expected 2 values, got \"spawn=1\"");
+        assert_eq!(parse_error("piece=A\nspawn=1 a\nX"), "can't parse \"a\" in \"spawn=1 a\"");
+        assert_eq!(parse_error("piece=A\nO+"), "state \"O+\" has two centers");
+        assert_eq!(parse_error("piece=A\n.+"), "state \".+\" has no tiles");
+        assert_eq!(parse_error("piece=A"), "piece A has 0 states, expected 1 to 4");
+        assert_eq!(parse_error("piece=A\nX\n\nX\n\nX\n\nX\n\nX"), "piece A has 5 states, expected 1 to 4");
+        assert_eq!(parse_error("name=Empty"), "no pieces");
+    }
+
+    #[test]
+    fn built_in_sets() {
+        let pentominoes = PieceSetType::Pentominoes.create();
+        assert_eq!(pentominoes.shapes.len(), 18);
+        assert!(pentominoes.shapes.iter().all(|shape| shape.states.iter().all(|state| state.len() == 5)));
+        assert_eq!(pentominoes.get_widest_piece(), 5);
+
+        let party = PieceSetType::TetrominoesAndMonomino.create();
+        assert_eq!(party.get_block_types().len(), 8);
+        assert_eq!(PieceSetType::Tetrominoes.create().get_widest_piece(), 0);
+    }
+
+    #[test]
+    fn spawn_stays_inside_the_walls() {
+        let piece_set = PieceSet::parse("piece=L\n...X\nXXOX\n\npiece=Far\nspawn=10 0\nO").unwrap();
+        let start = piece_set.shapes[0].get_start_position(5);
+        assert_eq!((start.x, start.y), (2, 1));
+        // center of a 4 wide field is column 1, L would stick out to the left
+        let start = piece_set.shapes[0].get_start_position(4);
+        assert_eq!((start.x, start.y), (2, 1));
+        let start = piece_set.shapes[1].get_start_position(10);
+        assert_eq!((start.x, start.y), (9, 1));
+    }
+}
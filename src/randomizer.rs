@@ -0,0 +1,228 @@
+use rand::{RngCore, Rng};
+use rand::seq::SliceRandom;
+use crate::tetronimoe::BlockType;
+
+// Randomizer decides which figure comes next.
+// Random numbers are taken from the game RNG, so randomizer itself keeps only its history.
+// Figures are picked from block_types, see piece_set::PieceSet::get_block_types.
+pub trait Randomizer {
+    fn get_name(&self) -> &'static str;
+
+    fn next_block_type(&mut self, block_types: &[BlockType], rng: &mut dyn RngCore) -> BlockType;
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum RandomizerType {
+    SevenBag, // guideline: every 7 figures contain each figure exactly once
+    FourteenBag, // same, but with two copies of each figure in the bag
+    Tgm, // TGM2: 4 figures history, up to 6 rolls to get figure that is not in it
+    Nes, // NES: one reroll if the same figure comes twice in a row
+    PureRandom, // every figure is equally possible, no matter what was before
+}
+
+impl RandomizerType {
+    pub fn create(&self) -> Box<dyn Randomizer> {
+        match self {
+            RandomizerType::SevenBag => Box::new(BagRandomizer::init(1)),
+            RandomizerType::FourteenBag => Box::new(BagRandomizer::init(2)),
+            RandomizerType::Tgm => Box::new(HistoryRandomizer::init(6)),
+            RandomizerType::Nes => Box::new(NesRandomizer { previous: None }),
+            RandomizerType::PureRandom => Box::new(PureRandomizer {}),
+        }
+    }
+
+    pub fn next(&self) -> RandomizerType {
+        match self {
+            RandomizerType::SevenBag => RandomizerType::FourteenBag,
+            RandomizerType::FourteenBag => RandomizerType::Tgm,
+            RandomizerType::Tgm => RandomizerType::Nes,
+            RandomizerType::Nes => RandomizerType::PureRandom,
+            RandomizerType::PureRandom => RandomizerType::SevenBag,
+        }
+    }
+}
+
+pub struct PureRandomizer {}
+
+impl Randomizer for PureRandomizer {
+    fn get_name(&self) -> &'static str {
+        "Random"
+    }
+
+    fn next_block_type(&mut self, block_types: &[BlockType], rng: &mut dyn RngCore) -> BlockType {
+        block_types[rng.gen_range(0, block_types.len())]
+    }
+}
+
+pub struct BagRandomizer {
+    copies: usize, // how many times each figure is put into the bag
+    bag: Vec<BlockType>,
+}
+
+impl BagRandomizer {
+    pub fn init(copies: usize) -> BagRandomizer {
+        BagRandomizer { copies, bag: vec![] }
+    }
+}
+
+impl Randomizer for BagRandomizer {
+    fn get_name(&self) -> &'static str {
+        match self.copies {
+            1 => "7-bag",
+            2 => "14-bag",
+            _ => "Bag",
+        }
+    }
+
+    fn next_block_type(&mut self, block_types: &[BlockType], rng: &mut dyn RngCore) -> BlockType {
+        if self.bag.is_empty() {
+            for _ in 0..self.copies {
+                self.bag.extend_from_slice(block_types);
+            }
+            self.bag.shuffle(rng);
+        }
+
+        self.bag.pop().unwrap()
+    }
+}
+
+pub struct HistoryRandomizer {
+    rolls: u32,
+    history: Vec<BlockType>, // last 4 figures, the oldest one goes first
+    is_first_figure: bool,
+}
+
+impl HistoryRandomizer {
+    pub fn init(rolls: u32) -> HistoryRandomizer {
+        HistoryRandomizer {
+            rolls,
+            // history starts filled with squiggles, so they are rare at the beginning
+            history: vec![BlockType::ReverseSquiggle, BlockType::Squiggle, BlockType::Squiggle, BlockType::ReverseSquiggle],
+            is_first_figure: true,
+        }
+    }
+}
+
+impl Randomizer for HistoryRandomizer {
+    fn get_name(&self) -> &'static str {
+        "TGM"
+    }
+
+    fn next_block_type(&mut self, block_types: &[BlockType], rng: &mut dyn RngCore) -> BlockType {
+        let block_type = if self.is_first_figure {
+            // first figure is never a squiggle or a square, so the game never starts with overhang
+            self.is_first_figure = false;
+            let first_types: Vec<BlockType> = [BlockType::LBlock, BlockType::ReverseLBlock, BlockType::TBlock, BlockType::LinePiece].iter()
+                .copied()
+                .filter(|block_type| block_types.contains(block_type))
+                .collect();
+            if first_types.is_empty() {
+                block_types[rng.gen_range(0, block_types.len())]
+            } else {
+                first_types[rng.gen_range(0, first_types.len())]
+            }
+        } else {
+            let mut block_type = block_types[rng.gen_range(0, block_types.len())];
+            for _ in 1..self.rolls {
+                if !self.history.contains(&block_type) {
+                    break;
+                }
+                block_type = block_types[rng.gen_range(0, block_types.len())];
+            }
+            block_type
+        };
+
+        self.history.remove(0);
+        self.history.push(block_type);
+        block_type
+    }
+}
+
+pub struct NesRandomizer {
+    previous: Option<BlockType>,
+}
+
+impl Randomizer for NesRandomizer {
+    fn get_name(&self) -> &'static str {
+        "NES"
+    }
+
+    // First roll has 8 outcomes, the extra one means "roll again" just like a repeat.
+    // Second roll is taken as is.
+    fn next_block_type(&mut self, block_types: &[BlockType], rng: &mut dyn RngCore) -> BlockType {
+        let roll = rng.gen_range(0, block_types.len() + 1);
+        let block_type = if roll == block_types.len() || self.previous == Some(block_types[roll]) {
+            block_types[rng.gen_range(0, block_types.len())]
+        } else {
+            block_types[roll]
+        };
+
+        self.previous = Some(block_type);
+        block_type
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tetronimoe::BLOCK_TYPES;
+    use crate::piece_set::PieceSetType;
+    use rand_chacha::ChaCha8Rng;
+    use rand::SeedableRng;
+
+    fn take(randomizer: &mut dyn Randomizer, block_types: &[BlockType], rng: &mut ChaCha8Rng, count: usize) -> Vec<BlockType> {
+        (0..count).map(|_| randomizer.next_block_type(block_types, rng)).collect()
+    }
+
+    fn assert_bags(sequence: &[BlockType], block_types: &[BlockType], copies: usize) {
+        for bag in sequence.chunks(block_types.len() * copies) {
+            for block_type in block_types.iter() {
+                assert_eq!(bag.iter().filter(|other| *other == block_type).count(), copies, "{:?} in {:?}", block_type, bag);
+            }
+        }
+    }
+
+    #[test]
+    fn bags() {
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        let sequence = take(RandomizerType::SevenBag.create().as_mut(), &BLOCK_TYPES, &mut rng, 70);
+        assert_bags(&sequence, &BLOCK_TYPES, 1);
+
+        let sequence = take(RandomizerType::FourteenBag.create().as_mut(), &BLOCK_TYPES, &mut rng, 140);
+        assert_bags(&sequence, &BLOCK_TYPES, 2);
+
+        let block_types = PieceSetType::Pentominoes.create().get_block_types();
+        let sequence = take(RandomizerType::SevenBag.create().as_mut(), &block_types, &mut rng, block_types.len() * 5);
+        assert_bags(&sequence, &block_types, 1);
+    }
+
+    #[test]
+    fn tgm_first_figure() {
+        let first_types = [BlockType::LBlock, BlockType::ReverseLBlock, BlockType::TBlock, BlockType::LinePiece];
+        for seed in 0..100 {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            let block_type = RandomizerType::Tgm.create().next_block_type(&BLOCK_TYPES, &mut rng);
+            assert!(first_types.contains(&block_type), "seed {} starts with {:?}", seed, block_type);
+        }
+
+        // piece sets without these figures start with anything
+        let block_types = [BlockType::Square, BlockType::Custom(0)];
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let block_type = RandomizerType::Tgm.create().next_block_type(&block_types, &mut rng);
+        assert!(block_types.contains(&block_type));
+    }
+
+    // Share of figures that are the same as the previous one
+    fn repeat_rate(randomizer_type: RandomizerType) -> f64 {
+        let mut rng = ChaCha8Rng::seed_from_u64(2);
+        let sequence = take(randomizer_type.create().as_mut(), &BLOCK_TYPES, &mut rng, 10000);
+        sequence.windows(2).filter(|pair| pair[0] == pair[1]).count() as f64 / 9999.0
+    }
+
+    #[test]
+    fn nes_reroll() {
+        // 1/7 without reroll, 1/28 with it
+        assert!((repeat_rate(RandomizerType::PureRandom) - 1.0 / 7.0).abs() < 0.02);
+        assert!((repeat_rate(RandomizerType::Nes) - 1.0 / 28.0).abs() < 0.01);
+    }
+}
@@ -0,0 +1,102 @@
+use crate::tetronimoe::{BlockType, Direction, Figure, Point};
+use crate::rotation_system::RotationSystem;
+use crate::collision_checker::{offset_is_clear, cell_is_free};
+use crate::field::Field;
+
+// Arika Rotation System from TGM series (TGM2 flavour: line piece never kicks).
+// Figures spawn flat side up and stick to the bottom of 3x3 bounding box,
+// squiggles and line piece have only two real orientations.
+// Kicks are simple: try rotation in place, then one block right, then one block left.
+pub struct ArikaRotationSystem {}
+
+const KICKS: [(isize, isize); 2] = [(1, 0), (-1, 0)];
+
+impl RotationSystem for ArikaRotationSystem {
+    fn get_name(&self) -> &'static str {
+        "ARS"
+    }
+
+    // Center is the central block of 3x3 bounding box,
+    // for line piece it is the second block in the second row of 4x4 box.
+    fn get_tiles_offsets(&self, block_type: BlockType, direction: Direction) -> Vec<(isize, isize)> {
+        match (block_type, direction) {
+            (BlockType::Square, _) => vec![(0, 0), (1, 0), (0, 1), (1, 1)],
+
+            (BlockType::LinePiece, Direction::Up)
+            | (BlockType::LinePiece, Direction::Down) => vec![(-1, 0), (0, 0), (1, 0), (2, 0)],
+            (BlockType::LinePiece, Direction::Right)
+            | (BlockType::LinePiece, Direction::Left) => vec![(1, -1), (1, 0), (1, 1), (1, 2)],
+
+            (BlockType::TBlock, Direction::Up) => vec![(0, 0), (-1, 0), (1, 0), (0, 1)],
+            (BlockType::TBlock, Direction::Right) => vec![(0, 0), (0, -1), (0, 1), (-1, 0)],
+            (BlockType::TBlock, Direction::Down) => vec![(0, 0), (-1, 1), (0, 1), (1, 1)],
+            (BlockType::TBlock, Direction::Left) => vec![(0, 0), (0, -1), (0, 1), (1, 0)],
+
+            (BlockType::LBlock, Direction::Up) => vec![(0, 0), (-1, 0), (1, 0), (-1, 1)],
+            (BlockType::LBlock, Direction::Right) => vec![(0, 0), (-1, -1), (0, -1), (0, 1)],
+            (BlockType::LBlock, Direction::Down) => vec![(1, 0), (-1, 1), (0, 1), (1, 1)],
+            (BlockType::LBlock, Direction::Left) => vec![(0, 0), (0, -1), (0, 1), (1, 1)],
+
+            (BlockType::ReverseLBlock, Direction::Up) => vec![(0, 0), (-1, 0), (1, 0), (1, 1)],
+            (BlockType::ReverseLBlock, Direction::Right) => vec![(0, 0), (0, -1), (0, 1), (-1, 1)],
+            (BlockType::ReverseLBlock, Direction::Down) => vec![(-1, 0), (-1, 1), (0, 1), (1, 1)],
+            (BlockType::ReverseLBlock, Direction::Left) => vec![(0, 0), (0, -1), (1, -1), (0, 1)],
+
+            (BlockType::Squiggle, Direction::Up)
+            | (BlockType::Squiggle, Direction::Down) => vec![(0, 0), (1, 0), (-1, 1), (0, 1)],
+            (BlockType::Squiggle, Direction::Right)
+            | (BlockType::Squiggle, Direction::Left) => vec![(0, 0), (-1, -1), (-1, 0), (0, 1)],
+
+            (BlockType::ReverseSquiggle, Direction::Up)
+            | (BlockType::ReverseSquiggle, Direction::Down) => vec![(0, 0), (-1, 0), (0, 1), (1, 1)],
+            (BlockType::ReverseSquiggle, Direction::Right)
+            | (BlockType::ReverseSquiggle, Direction::Left) => vec![(0, 0), (1, -1), (1, 0), (0, 1)],
+
+            // custom figures are handled by custom::CustomPiecesRotationSystem
+            (BlockType::Custom(_), _) | (_, Direction::None) => unreachable!(),
+        }
+    }
+
+    // Spawn orientation occupies two middle rows of the bounding box,
+    // so center on the top row puts figure into two rows of the spawn area
+    fn get_start_position(&self, _block_type: BlockType, field_width: usize) -> Point {
+        Point {x: ((field_width - 1) / 2) as isize, y: 0}
+    }
+
+    fn resolve_rotation(&self, rotated_figure: Figure, _previous_direction: Direction, field: &Field) -> Option<Figure> {
+        if offset_is_clear(&rotated_figure, 0, 0, field) {
+            return Some(rotated_figure);
+        }
+
+        let kicks_allowed = match rotated_figure.block_type {
+            BlockType::LinePiece | BlockType::Square | BlockType::Custom(_) => false,
+            BlockType::TBlock | BlockType::LBlock | BlockType::ReverseLBlock => !center_column_is_blocked(&rotated_figure, field),
+            BlockType::Squiggle | BlockType::ReverseSquiggle => true,
+        };
+        if !kicks_allowed {
+            return None;
+        }
+
+        for (x, y) in KICKS.iter() {
+            if offset_is_clear(&rotated_figure, *x, *y, field) {
+                let mut kicked_figure = rotated_figure;
+                kicked_figure.shift(*x, *y);
+                return Some(kicked_figure);
+            }
+        }
+
+        None
+    }
+}
+
+// L, J and T do not kick if the first blocked cell (reading bounding box row by row, left to right)
+// is in the central column. Otherwise they could climb through narrow holes.
+fn center_column_is_blocked(rotated_figure: &Figure, field: &Field) -> bool {
+    let center = rotated_figure.center;
+    let mut tiles = rotated_figure.tiles.clone();
+    tiles.sort_by_key(|point| (point.y, point.x));
+    match tiles.iter().find(|point| !cell_is_free(point.x, point.y, field)) {
+        Some(point) => point.x == center.x,
+        None => false,
+    }
+}
@@ -0,0 +1,46 @@
+use crate::tetronimoe::{BlockType, Direction, Figure, Point};
+use crate::rotation_system::RotationSystem;
+use crate::collision_checker::offset_is_clear;
+use crate::piece_set::PieceSet;
+use crate::field::Field;
+
+// Kicks for figures from piece set files: sideways first, then one row up
+const CUSTOM_KICKS: [(isize, isize); 6] = [(0, 0), (-1, 0), (1, 0), (0, -1), (-2, 0), (2, 0)];
+
+// Adds figures of the piece set to another rotation system.
+// Standard figures still follow the wrapped system, custom ones take shapes from the piece set.
+pub struct CustomPiecesRotationSystem {
+    pub standard: Box<dyn RotationSystem>,
+    pub piece_set: PieceSet,
+}
+
+impl RotationSystem for CustomPiecesRotationSystem {
+    fn get_name(&self) -> &'static str {
+        self.standard.get_name()
+    }
+
+    fn get_tiles_offsets(&self, block_type: BlockType, direction: Direction) -> Vec<(isize, isize)> {
+        match block_type {
+            BlockType::Custom(index) => self.piece_set.shapes[index].get_tiles_offsets(direction),
+            _ => self.standard.get_tiles_offsets(block_type, direction),
+        }
+    }
+
+    fn get_start_position(&self, block_type: BlockType, field_width: usize) -> Point {
+        match block_type {
+            BlockType::Custom(index) => self.piece_set.shapes[index].get_start_position(field_width),
+            _ => self.standard.get_start_position(block_type, field_width),
+        }
+    }
+
+    fn resolve_rotation(&self, rotated_figure: Figure, previous_direction: Direction, field: &Field) -> Option<Figure> {
+        if let BlockType::Custom(_) = rotated_figure.block_type {
+            let (x, y) = *CUSTOM_KICKS.iter().find(|(x, y)| offset_is_clear(&rotated_figure, *x, *y, field))?;
+            let mut kicked_figure = rotated_figure;
+            kicked_figure.shift(x, y);
+            return Some(kicked_figure);
+        }
+
+        self.standard.resolve_rotation(rotated_figure, previous_direction, field)
+    }
+}
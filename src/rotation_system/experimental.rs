@@ -0,0 +1,98 @@
+use crate::tetronimoe::{BlockType, Direction, Figure, Point};
+use crate::rotation_system::RotationSystem;
+use crate::collision_checker::offset_is_clear;
+use crate::field::Field;
+
+// The very first rotation system of this game.
+// It does not follow SRS or anything else, I just decided to make an experiment with it.
+// Figure rotates around its center, if it gets into the wall, it is pushed back by one or two blocks.
+pub struct ExperimentalRotationSystem {}
+
+impl RotationSystem for ExperimentalRotationSystem {
+    fn get_name(&self) -> &'static str {
+        "Experimental"
+    }
+
+    // It is asymmetrical, but full circle CW/CCW rotation will held figure intact
+    // Center position depends on figure type:
+    //   * Square - left lower block, just because
+    //   * Squiggle - lower central block(i.e. right square in lower row)
+    //   * ReverseSquiggle - lower central block (i.e. left square in lower row)
+    //   * LBlock - block connecting | and _
+    //   * ReverseLBlock - block connecting | and _
+    //   * TBlock - block connecting | and _
+    //   * LinePiece - second block from below
+    fn get_tiles_offsets(&self, block_type: BlockType, direction: Direction) -> Vec<(isize, isize)> {
+        match (block_type, direction) {
+            (BlockType::Square, _) => vec![(0, 0), (0, -1), (1, -1), (1, 0)],
+
+            (BlockType::Squiggle, Direction::Up) => vec![(0, 0), (0, 1), (-1, -1), (-1, 0)],
+            (BlockType::Squiggle, Direction::Right) => vec![(0, 0), (-1, 0), (1, -1), (0, -1)],
+            (BlockType::Squiggle, Direction::Down) => vec![(0, 0), (0, -1), (1, 1), (1, 0)],
+            (BlockType::Squiggle, Direction::Left) => vec![(0, 0), (1, 0), (-1, 1), (0, 1)],
+
+            (BlockType::ReverseSquiggle, Direction::Up) => vec![(0, 0), (0, 1), (1, 0), (1, -1)],
+            (BlockType::ReverseSquiggle, Direction::Right) => vec![(0, 0), (1, 0), (0, -1), (-1, -1)],
+            (BlockType::ReverseSquiggle, Direction::Down) => vec![(0, 0), (0, -1), (-1, 0), (-1, 1)],
+            (BlockType::ReverseSquiggle, Direction::Left) => vec![(0, 0), (-1, 0), (0, 1), (1, 1)],
+
+            (BlockType::LBlock, Direction::Up) => vec![(0, 0), (1, 0), (0, -1), (0, -2)],
+            (BlockType::LBlock, Direction::Right) => vec![(0, 0), (0, 1), (1, 0), (2, 0)],
+            (BlockType::LBlock, Direction::Down) => vec![(0, 0), (-1, 0), (0, 1), (0, 2)],
+            (BlockType::LBlock, Direction::Left) => vec![(0, 0), (0, -1), (-1, 0), (-2, 0)],
+
+            (BlockType::ReverseLBlock, Direction::Up) => vec![(0, 0), (-1, 0), (0, -1), (0, -2)],
+            (BlockType::ReverseLBlock, Direction::Right) => vec![(0, 0), (0, -1), (1, 0), (2, 0)],
+            (BlockType::ReverseLBlock, Direction::Down) => vec![(0, 0), (1, 0), (0, 1), (0, 2)],
+            (BlockType::ReverseLBlock, Direction::Left) => vec![(0, 0), (0, 1), (-1, 0), (-2, 0)],
+
+            (BlockType::TBlock, Direction::Up) => vec![(0, 0), (-1, 0), (1, 0), (0, -1)],
+            (BlockType::TBlock, Direction::Right) => vec![(0, 0), (0, -1), (0, 1), (1, 0)],
+            (BlockType::TBlock, Direction::Down) => vec![(0, 0), (1, 0), (-1, 0), (0, 1)],
+            (BlockType::TBlock, Direction::Left) => vec![(0, 0), (0, 1), (0, -1), (-1, 0)],
+
+            (BlockType::LinePiece, Direction::Up) => vec![(0, 0), (0, -1), (0, 1), (0, 2)],
+            (BlockType::LinePiece, Direction::Right) => vec![(0, 0), (1, 0), (-1, 0), (-2, 0)],
+            (BlockType::LinePiece, Direction::Down) => vec![(0, 0), (0, 1), (0, -1), (0, -2)],
+            (BlockType::LinePiece, Direction::Left) => vec![(0, 0), (-1, 0), (1, 0), (2, 0)],
+
+            // custom figures are handled by custom::CustomPiecesRotationSystem
+            (BlockType::Custom(_), _) | (_, Direction::None) => unreachable!(),
+        }
+    }
+
+    fn get_start_position(&self, _block_type: BlockType, field_width: usize) -> Point {
+        Point {x: (field_width / 2) as isize, y: 2}
+    }
+
+    fn resolve_rotation(&self, rotated_figure: Figure, _previous_direction: Direction, field: &Field) -> Option<Figure> {
+        let mut left_x = rotated_figure.center.x;
+        let mut right_x = rotated_figure.center.x;
+        for point in &rotated_figure.tiles {
+            // lets just assume that we can't rotate figure if it is too low
+            if point.y < 0 {
+                return None;
+            }
+
+            left_x = left_x.min(point.x);
+            right_x = right_x.max(point.x);
+        }
+
+        // shift right or left by one or two blocks if figure got into the wall
+        let shift = if left_x < 0 {
+            -left_x
+        } else if right_x >= field.width as isize {
+            field.width as isize - 1 - right_x
+        } else {
+            0
+        };
+
+        if !offset_is_clear(&rotated_figure, shift, 0, field) {
+            return None;
+        }
+
+        let mut shifted_figure = rotated_figure;
+        shifted_figure.shift(shift, 0);
+        Some(shifted_figure)
+    }
+}
@@ -0,0 +1,63 @@
+use crate::tetronimoe::{BlockType, Direction, Figure, Point};
+use crate::field::Field;
+use crate::piece_set::PieceSet;
+
+pub mod srs;
+pub mod ars;
+pub mod experimental;
+pub mod custom;
+
+// Rotation system defines how figures look in every orientation
+// and what happens when rotated figure collides with walls or blocks.
+pub trait RotationSystem {
+    fn get_name(&self) -> &'static str;
+
+    // Tiles of the figure as offsets from its center.
+    // y axis looks down, so -1 is the row above the center.
+    fn get_tiles_offsets(&self, block_type: BlockType, direction: Direction) -> Vec<(isize, isize)>;
+
+    // Center of a freshly spawned figure, spawn orientation is always Direction::Up.
+    // Position is relative to the spawn area: two rows right above the visible field.
+    fn get_start_position(&self, block_type: BlockType, field_width: usize) -> Point;
+
+    // Takes figure that is already rotated in place and returns its final position
+    // after kicks, or None if rotation is impossible
+    fn resolve_rotation(&self, rotated_figure: Figure, previous_direction: Direction, field: &Field) -> Option<Figure>;
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum RotationSystemType {
+    Srs, // Super Rotation System, used by most of modern games
+    Ars, // Arika Rotation System, TGM series
+    Experimental, // first rotation system of this game, not compatible with anything
+}
+
+impl RotationSystemType {
+    pub fn create(&self) -> Box<dyn RotationSystem> {
+        match self {
+            RotationSystemType::Srs => Box::new(srs::SuperRotationSystem {}),
+            RotationSystemType::Ars => Box::new(ars::ArikaRotationSystem {}),
+            RotationSystemType::Experimental => Box::new(experimental::ExperimentalRotationSystem {}),
+        }
+    }
+
+    // Rotation system that also knows figures of the piece set
+    pub fn create_for_piece_set(&self, piece_set: &PieceSet) -> Box<dyn RotationSystem> {
+        if piece_set.shapes.is_empty() {
+            return self.create();
+        }
+
+        Box::new(custom::CustomPiecesRotationSystem {
+            standard: self.create(),
+            piece_set: piece_set.clone(),
+        })
+    }
+
+    pub fn next(&self) -> RotationSystemType {
+        match self {
+            RotationSystemType::Srs => RotationSystemType::Ars,
+            RotationSystemType::Ars => RotationSystemType::Experimental,
+            RotationSystemType::Experimental => RotationSystemType::Srs,
+        }
+    }
+}
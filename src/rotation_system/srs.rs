@@ -0,0 +1,206 @@
+use crate::tetronimoe::{BlockType, Direction, Figure, Point};
+use crate::rotation_system::RotationSystem;
+use crate::collision_checker::offset_is_clear;
+use crate::field::Field;
+
+// Super Rotation System, as described by the Tetris guideline.
+// Every figure rotates around its bounding box center,
+// line piece and square have their true centers between blocks.
+pub struct SuperRotationSystem {}
+
+// SRS wall kick tables.
+// Offsets are tested one by one after rotation, first one that fits is used.
+// Tables are usually written with y axis looking up, here y looks down like everywhere in the game,
+// so all y values have opposite sign compared to the guideline.
+
+const NO_KICKS: [(isize, isize); 1] = [(0, 0)];
+
+const JLSTZ_UP_TO_RIGHT: [(isize, isize); 5] = [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)];
+const JLSTZ_RIGHT_TO_UP: [(isize, isize); 5] = [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)];
+const JLSTZ_RIGHT_TO_DOWN: [(isize, isize); 5] = [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)];
+const JLSTZ_DOWN_TO_RIGHT: [(isize, isize); 5] = [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)];
+const JLSTZ_DOWN_TO_LEFT: [(isize, isize); 5] = [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)];
+const JLSTZ_LEFT_TO_DOWN: [(isize, isize); 5] = [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)];
+const JLSTZ_LEFT_TO_UP: [(isize, isize); 5] = [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)];
+const JLSTZ_UP_TO_LEFT: [(isize, isize); 5] = [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)];
+
+const I_UP_TO_RIGHT: [(isize, isize); 5] = [(0, 0), (-2, 0), (1, 0), (-2, 1), (1, -2)];
+const I_RIGHT_TO_UP: [(isize, isize); 5] = [(0, 0), (2, 0), (-1, 0), (2, -1), (-1, 2)];
+const I_RIGHT_TO_DOWN: [(isize, isize); 5] = [(0, 0), (-1, 0), (2, 0), (-1, -2), (2, 1)];
+const I_DOWN_TO_RIGHT: [(isize, isize); 5] = [(0, 0), (1, 0), (-2, 0), (1, 2), (-2, -1)];
+const I_DOWN_TO_LEFT: [(isize, isize); 5] = [(0, 0), (2, 0), (-1, 0), (2, -1), (-1, 2)];
+const I_LEFT_TO_DOWN: [(isize, isize); 5] = [(0, 0), (-2, 0), (1, 0), (-2, 1), (1, -2)];
+const I_LEFT_TO_UP: [(isize, isize); 5] = [(0, 0), (1, 0), (-2, 0), (1, 2), (-2, -1)];
+const I_UP_TO_LEFT: [(isize, isize); 5] = [(0, 0), (-1, 0), (2, 0), (-1, -2), (2, 1)];
+
+fn get_kicks(block_type: BlockType, from: Direction, to: Direction) -> &'static [(isize, isize)] {
+    match block_type {
+        BlockType::Square => &NO_KICKS,
+        BlockType::LinePiece => match (from, to) {
+            (Direction::Up, Direction::Right) => &I_UP_TO_RIGHT,
+            (Direction::Right, Direction::Up) => &I_RIGHT_TO_UP,
+            (Direction::Right, Direction::Down) => &I_RIGHT_TO_DOWN,
+            (Direction::Down, Direction::Right) => &I_DOWN_TO_RIGHT,
+            (Direction::Down, Direction::Left) => &I_DOWN_TO_LEFT,
+            (Direction::Left, Direction::Down) => &I_LEFT_TO_DOWN,
+            (Direction::Left, Direction::Up) => &I_LEFT_TO_UP,
+            (Direction::Up, Direction::Left) => &I_UP_TO_LEFT,
+            _ => &NO_KICKS,
+        },
+        _ => match (from, to) {
+            (Direction::Up, Direction::Right) => &JLSTZ_UP_TO_RIGHT,
+            (Direction::Right, Direction::Up) => &JLSTZ_RIGHT_TO_UP,
+            (Direction::Right, Direction::Down) => &JLSTZ_RIGHT_TO_DOWN,
+            (Direction::Down, Direction::Right) => &JLSTZ_DOWN_TO_RIGHT,
+            (Direction::Down, Direction::Left) => &JLSTZ_DOWN_TO_LEFT,
+            (Direction::Left, Direction::Down) => &JLSTZ_LEFT_TO_DOWN,
+            (Direction::Left, Direction::Up) => &JLSTZ_LEFT_TO_UP,
+            (Direction::Up, Direction::Left) => &JLSTZ_UP_TO_LEFT,
+            _ => &NO_KICKS,
+        },
+    }
+}
+
+impl RotationSystem for SuperRotationSystem {
+    fn get_name(&self) -> &'static str {
+        "SRS"
+    }
+
+    // Center position depends on figure type:
+    //   * Square - left lower block, it does not rotate anyway
+    //   * LinePiece - second block from the left in spawn orientation.
+    //     Real rotation point is between blocks, so center moves a bit during rotation
+    //   * All others - central block of 3x3 bounding box
+    fn get_tiles_offsets(&self, block_type: BlockType, direction: Direction) -> Vec<(isize, isize)> {
+        match (block_type, direction) {
+            (BlockType::Square, _) => vec![(0, 0), (0, -1), (1, -1), (1, 0)],
+
+            (BlockType::LinePiece, Direction::Up) => vec![(-1, 0), (0, 0), (1, 0), (2, 0)],
+            (BlockType::LinePiece, Direction::Right) => vec![(1, -1), (1, 0), (1, 1), (1, 2)],
+            (BlockType::LinePiece, Direction::Down) => vec![(-1, 1), (0, 1), (1, 1), (2, 1)],
+            (BlockType::LinePiece, Direction::Left) => vec![(0, -1), (0, 0), (0, 1), (0, 2)],
+
+            (BlockType::TBlock, Direction::Up) => vec![(0, 0), (-1, 0), (1, 0), (0, -1)],
+            (BlockType::TBlock, Direction::Right) => vec![(0, 0), (0, -1), (0, 1), (1, 0)],
+            (BlockType::TBlock, Direction::Down) => vec![(0, 0), (-1, 0), (1, 0), (0, 1)],
+            (BlockType::TBlock, Direction::Left) => vec![(0, 0), (0, -1), (0, 1), (-1, 0)],
+
+            (BlockType::LBlock, Direction::Up) => vec![(0, 0), (-1, 0), (1, 0), (1, -1)],
+            (BlockType::LBlock, Direction::Right) => vec![(0, 0), (0, -1), (0, 1), (1, 1)],
+            (BlockType::LBlock, Direction::Down) => vec![(0, 0), (-1, 0), (1, 0), (-1, 1)],
+            (BlockType::LBlock, Direction::Left) => vec![(0, 0), (0, -1), (0, 1), (-1, -1)],
+
+            (BlockType::ReverseLBlock, Direction::Up) => vec![(0, 0), (-1, 0), (1, 0), (-1, -1)],
+            (BlockType::ReverseLBlock, Direction::Right) => vec![(0, 0), (0, -1), (0, 1), (1, -1)],
+            (BlockType::ReverseLBlock, Direction::Down) => vec![(0, 0), (-1, 0), (1, 0), (1, 1)],
+            (BlockType::ReverseLBlock, Direction::Left) => vec![(0, 0), (0, -1), (0, 1), (-1, 1)],
+
+            (BlockType::Squiggle, Direction::Up) => vec![(0, 0), (-1, 0), (0, -1), (1, -1)],
+            (BlockType::Squiggle, Direction::Right) => vec![(0, 0), (0, -1), (1, 0), (1, 1)],
+            (BlockType::Squiggle, Direction::Down) => vec![(0, 0), (1, 0), (0, 1), (-1, 1)],
+            (BlockType::Squiggle, Direction::Left) => vec![(0, 0), (0, 1), (-1, 0), (-1, -1)],
+
+            (BlockType::ReverseSquiggle, Direction::Up) => vec![(0, 0), (1, 0), (0, -1), (-1, -1)],
+            (BlockType::ReverseSquiggle, Direction::Right) => vec![(0, 0), (0, 1), (1, 0), (1, -1)],
+            (BlockType::ReverseSquiggle, Direction::Down) => vec![(0, 0), (-1, 0), (0, 1), (1, 1)],
+            (BlockType::ReverseSquiggle, Direction::Left) => vec![(0, 0), (0, -1), (-1, 0), (-1, 1)],
+
+            // custom figures are handled by custom::CustomPiecesRotationSystem
+            (BlockType::Custom(_), _) | (_, Direction::None) => unreachable!(),
+        }
+    }
+
+    // Figures appear in two rows of the spawn area, in the middle of the field
+    // (left middle column for odd-width figures)
+    fn get_start_position(&self, _block_type: BlockType, field_width: usize) -> Point {
+        Point {x: ((field_width - 1) / 2) as isize, y: 1}
+    }
+
+    fn resolve_rotation(&self, rotated_figure: Figure, previous_direction: Direction, field: &Field) -> Option<Figure> {
+        for (x, y) in get_kicks(rotated_figure.block_type, previous_direction, rotated_figure.direction) {
+            if offset_is_clear(&rotated_figure, *x, *y, field) {
+                let mut kicked_figure = rotated_figure;
+                kicked_figure.shift(*x, *y);
+                return Some(kicked_figure);
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tetronimoe::{BlockType, Direction, Figure, Point};
+    use crate::game_state::GameState;
+    use crate::game_settings::GameSettings;
+    use crate::events::GameEvent;
+    use crate::tspin::TSpin;
+
+    // Default field is 10x40, the bottom row is 39
+    fn init_game(filled_rows: &[(usize, &str)], block_type: BlockType, center: Point) -> GameState {
+        let mut state = GameState::init(GameSettings::default(), 0);
+        let mut field = state.field.clone();
+        field.clear();
+        for (y, row) in filled_rows {
+            for (x, cell) in row.chars().enumerate() {
+                field.set_filled(x, *y, cell == 'X');
+            }
+        }
+        state.store_field(field);
+        state.current_figure = Figure::init(block_type, Direction::Up, center, state.rotation_system.as_ref());
+        state
+    }
+
+    #[test]
+    fn t_spin_triple_uses_the_last_kick() {
+        let mut state = init_game(&[
+            (35, ".X........"),
+            (36, "X........."),
+            (37, "X.XXXXXXXX"),
+            (38, "X..XXXXXXX"),
+            (39, "X.XXXXXXXX"),
+        ], BlockType::TBlock, Point {x: 2, y: 36});
+
+        state.rotate_clockwise();
+        assert_eq!(state.current_figure.direction, Direction::Right);
+        assert_eq!((state.current_figure.center.x, state.current_figure.center.y), (1, 38));
+        assert_eq!(state.last_rotation_kick, (-1, 2));
+
+        state.drain_events();
+        state.hard_drop();
+        assert!(state.drain_events().contains(&GameEvent::LinesCleared { rows: vec![37, 38, 39], tspin: TSpin::Full }));
+    }
+
+    #[test]
+    fn floor_kick_moves_figure_up() {
+        let mut state = init_game(&[], BlockType::TBlock, Point {x: 4, y: 39});
+
+        state.rotate_clockwise();
+        assert_eq!(state.current_figure.direction, Direction::Right);
+        assert_eq!((state.current_figure.center.x, state.current_figure.center.y), (3, 38));
+        assert_eq!(state.last_rotation_kick, (-1, -1));
+    }
+
+    #[test]
+    fn line_piece_wall_kick() {
+        let mut state = init_game(&[], BlockType::LinePiece, Point {x: 1, y: 30});
+        state.rotate_clockwise();
+        // vertical line piece right at the left wall
+        state.current_figure.shift(-2, 0);
+        assert!(state.current_figure.tiles.iter().all(|tile| tile.x == 0));
+
+        state.rotate_clockwise();
+        assert_eq!(state.current_figure.direction, Direction::Down);
+        assert_eq!((state.current_figure.center.x, state.current_figure.center.y), (1, 30));
+        assert_eq!(state.last_rotation_kick, (2, 0));
+    }
+
+    #[test]
+    fn square_does_not_kick() {
+        let mut state = init_game(&[], BlockType::Square, Point {x: 4, y: 30});
+        state.rotate_clockwise();
+        assert_eq!((state.current_figure.center.x, state.current_figure.center.y), (4, 30));
+        assert_eq!(state.last_rotation_kick, (0, 0));
+    }
+}
@@ -0,0 +1,124 @@
+use std::fs;
+use std::io;
+use std::fmt::Debug;
+use crate::randomizer::RandomizerType;
+use crate::piece_set::PieceSetType;
+use crate::rotation_system::RotationSystemType;
+
+// Data kept between launches of the game.
+// Stored as plain text, one "key=value" per line, unknown keys are ignored.
+pub struct SaveData {
+    pub best_score: u64,
+    pub last_seed: Option<u64>, // seed of the last finished game, to replay it
+    // Settings that change the sequence of figures for the same seed.
+    // None in files saved before they were stored, then the current ones are used.
+    pub last_randomizer: Option<RandomizerType>,
+    pub last_piece_set: Option<PieceSetType>,
+    pub last_rotation_system: Option<RotationSystemType>,
+}
+
+impl SaveData {
+    pub fn init() -> SaveData {
+        SaveData {
+            best_score: 0,
+            last_seed: None,
+            last_randomizer: None,
+            last_piece_set: None,
+            last_rotation_system: None,
+        }
+    }
+
+    // Missing or broken file is not an error, just nothing was saved yet
+    pub fn load(path: &str) -> SaveData {
+        match fs::read_to_string(path) {
+            Ok(content) => SaveData::parse(&content),
+            Err(_) => SaveData::init(),
+        }
+    }
+
+    pub fn parse(content: &str) -> SaveData {
+        let mut save_data = SaveData::init();
+        for line in content.lines() {
+            let mut parts = line.splitn(2, '=');
+            let key = parts.next().unwrap_or("").trim();
+            let value = parts.next().unwrap_or("").trim();
+            match key {
+                "best_score" => save_data.best_score = value.parse().unwrap_or(0),
+                "last_seed" => save_data.last_seed = value.parse().ok(),
+                "last_randomizer" => save_data.last_randomizer = parse_type(value, RandomizerType::SevenBag, RandomizerType::next),
+                "last_piece_set" => save_data.last_piece_set = parse_type(value, PieceSetType::Tetrominoes, PieceSetType::next),
+                "last_rotation_system" => save_data.last_rotation_system = parse_type(value, RotationSystemType::Srs, RotationSystemType::next),
+                _ => (),
+            }
+        }
+
+        save_data
+    }
+
+    pub fn store(&self, path: &str) -> io::Result<()> {
+        fs::write(path, self.to_text())
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut content = format!("best_score={}\n", self.best_score);
+        if let Some(seed) = self.last_seed {
+            content.push_str(&format!("last_seed={}\n", seed));
+        }
+        if let Some(randomizer) = self.last_randomizer {
+            content.push_str(&format!("last_randomizer={:?}\n", randomizer));
+        }
+        if let Some(piece_set) = self.last_piece_set {
+            content.push_str(&format!("last_piece_set={:?}\n", piece_set));
+        }
+        if let Some(rotation_system) = self.last_rotation_system {
+            content.push_str(&format!("last_rotation_system={:?}\n", rotation_system));
+        }
+
+        content
+    }
+}
+
+// Settings types are stored by their variant names, all of them are found by cycling with next()
+fn parse_type<T: Copy + Debug + PartialEq>(value: &str, first: T, next: fn(&T) -> T) -> Option<T> {
+    let mut setting = first;
+    loop {
+        if format!("{:?}", setting) == value {
+            return Some(setting);
+        }
+        setting = next(&setting);
+        if setting == first {
+            return None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replay_settings_are_kept() {
+        let mut save_data = SaveData::init();
+        save_data.best_score = 1200;
+        save_data.last_seed = Some(7);
+        save_data.last_randomizer = Some(RandomizerType::Nes);
+        save_data.last_piece_set = Some(PieceSetType::TetrominoesAndMonomino);
+        save_data.last_rotation_system = Some(RotationSystemType::Experimental);
+
+        let loaded = SaveData::parse(&save_data.to_text());
+        assert_eq!(loaded.best_score, 1200);
+        assert_eq!(loaded.last_seed, Some(7));
+        assert_eq!(loaded.last_randomizer, Some(RandomizerType::Nes));
+        assert_eq!(loaded.last_piece_set, Some(PieceSetType::TetrominoesAndMonomino));
+        assert_eq!(loaded.last_rotation_system, Some(RotationSystemType::Experimental));
+    }
+
+    #[test]
+    fn old_and_broken_files() {
+        let loaded = SaveData::parse("best_score=10\nlast_seed=7\nlast_randomizer=Unknown\n");
+        assert_eq!(loaded.last_seed, Some(7));
+        assert_eq!(loaded.last_randomizer, None);
+        assert_eq!(loaded.last_piece_set, None);
+        assert_eq!(loaded.last_rotation_system, None);
+    }
+}
@@ -0,0 +1,162 @@
+use crate::tspin::TSpin;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ScoringType {
+    Guideline, // T-spins, back-to-back, combos, drop points
+    Nes, // 40/100/300/1200 table, lines only
+}
+
+impl ScoringType {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            ScoringType::Guideline => "Guideline",
+            ScoringType::Nes => "NES",
+        }
+    }
+
+    // Points for a locked figure.
+    // combo is the number of line clears in a row before this one (0 for the first clear),
+    // is_back_to_back is true if this clear continues a chain of difficult clears.
+    pub fn get_lock_score(&self, cleared_lines_count: u32, tspin: TSpin, level: u32, combo: u32, is_back_to_back: bool) -> u64 {
+        match self {
+            ScoringType::Guideline => get_guideline_lock_score(cleared_lines_count, tspin, level, combo, is_back_to_back),
+            ScoringType::Nes => get_nes_lock_score(cleared_lines_count, level),
+        }
+    }
+
+    // Bonus for clearing the whole field, added on top of the lock score
+    pub fn get_perfect_clear_bonus(&self, cleared_lines_count: u32, level: u32, is_back_to_back: bool) -> u64 {
+        let bonus_base: u64 = match (self, cleared_lines_count, is_back_to_back) {
+            (ScoringType::Nes, _, _) => 0,
+            (ScoringType::Guideline, 0, _) => 0,
+            (ScoringType::Guideline, 1, _) => 800,
+            (ScoringType::Guideline, 2, _) => 1200,
+            (ScoringType::Guideline, 3, _) => 1800,
+            (ScoringType::Guideline, _, false) => 2000,
+            (ScoringType::Guideline, _, true) => 3200,
+        };
+
+        bonus_base * level as u64
+    }
+
+    pub fn get_soft_drop_score(&self, rows: u32) -> u64 {
+        rows as u64
+    }
+
+    // NES has no hard drop, so it is paid like a soft drop
+    pub fn get_hard_drop_score(&self, rows: u32) -> u64 {
+        match self {
+            ScoringType::Guideline => 2 * rows as u64,
+            ScoringType::Nes => rows as u64,
+        }
+    }
+}
+
+// Tetris and any T-spin that clears lines are difficult, they keep back-to-back chain alive
+pub fn is_difficult_clear(cleared_lines_count: u32, tspin: TSpin) -> bool {
+    cleared_lines_count >= 4 || (cleared_lines_count > 0 && tspin != TSpin::None)
+}
+
+fn get_guideline_lock_score(cleared_lines_count: u32, tspin: TSpin, level: u32, combo: u32, is_back_to_back: bool) -> u64 {
+    let score_base: u64 = match (tspin, cleared_lines_count) {
+        (TSpin::None, 0) => 0,
+        (TSpin::None, 1) => 100,
+        (TSpin::None, 2) => 300,
+        (TSpin::None, 3) => 500,
+        (TSpin::None, _) => 800,
+        (TSpin::Mini, 0) => 100,
+        (TSpin::Mini, 1) => 200,
+        (TSpin::Mini, _) => 400,
+        (TSpin::Full, 0) => 400,
+        (TSpin::Full, 1) => 800,
+        (TSpin::Full, 2) => 1200,
+        (TSpin::Full, _) => 1600,
+    };
+
+    let mut score = score_base * level as u64;
+    if is_back_to_back {
+        score = score * 3 / 2;
+    }
+    if cleared_lines_count > 0 {
+        score += 50 * combo as u64 * level as u64;
+    }
+
+    score
+}
+
+fn get_nes_lock_score(cleared_lines_count: u32, level: u32) -> u64 {
+    let score_base: u64 = match cleared_lines_count {
+        0 => 0,
+        1 => 40,
+        2 => 100,
+        3 => 300,
+        _ => 1200,
+    };
+
+    score_base * (level + 1) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lock_score() {
+        // scoring, lines, T-spin, level, combo, back-to-back, score
+        let table = [
+            (ScoringType::Guideline, 0, TSpin::None, 1, 0, false, 0),
+            (ScoringType::Guideline, 1, TSpin::None, 1, 0, false, 100),
+            (ScoringType::Guideline, 4, TSpin::None, 2, 0, false, 1600),
+            (ScoringType::Guideline, 4, TSpin::None, 2, 0, true, 2400),
+            (ScoringType::Guideline, 1, TSpin::Mini, 1, 0, true, 300),
+            (ScoringType::Guideline, 3, TSpin::Full, 3, 0, true, 7200),
+            // combo counts only for line clears and is not multiplied by back-to-back
+            (ScoringType::Guideline, 0, TSpin::Full, 2, 5, false, 800),
+            (ScoringType::Guideline, 2, TSpin::None, 3, 4, false, 1500),
+            (ScoringType::Guideline, 4, TSpin::None, 1, 2, true, 1300),
+            // NES level 0 is level 1 here, T-spins, combos and back-to-back are ignored
+            (ScoringType::Nes, 0, TSpin::None, 1, 0, false, 0),
+            (ScoringType::Nes, 1, TSpin::None, 1, 0, false, 80),
+            (ScoringType::Nes, 2, TSpin::Mini, 2, 1, false, 300),
+            (ScoringType::Nes, 4, TSpin::Full, 5, 3, true, 7200),
+        ];
+
+        for (scoring, lines, tspin, level, combo, is_back_to_back, score) in table.iter() {
+            assert_eq!(scoring.get_lock_score(*lines, *tspin, *level, *combo, *is_back_to_back), *score,
+                "{:?} {} lines {:?} level {} combo {} b2b {}", scoring, lines, tspin, level, combo, is_back_to_back);
+        }
+    }
+
+    #[test]
+    fn perfect_clear_bonus() {
+        // scoring, lines, level, back-to-back, bonus
+        let table = [
+            (ScoringType::Guideline, 0, 1, false, 0),
+            (ScoringType::Guideline, 1, 2, false, 1600),
+            (ScoringType::Guideline, 3, 1, true, 1800),
+            (ScoringType::Guideline, 4, 3, false, 6000),
+            (ScoringType::Guideline, 4, 1, true, 3200),
+            (ScoringType::Nes, 4, 1, true, 0),
+        ];
+
+        for (scoring, lines, level, is_back_to_back, bonus) in table.iter() {
+            assert_eq!(scoring.get_perfect_clear_bonus(*lines, *level, *is_back_to_back), *bonus,
+                "{:?} {} lines level {} b2b {}", scoring, lines, level, is_back_to_back);
+        }
+    }
+
+    #[test]
+    fn drop_score() {
+        assert_eq!(ScoringType::Guideline.get_soft_drop_score(5), 5);
+        assert_eq!(ScoringType::Guideline.get_hard_drop_score(5), 10);
+        assert_eq!(ScoringType::Nes.get_hard_drop_score(5), 5);
+    }
+
+    #[test]
+    fn difficult_clears() {
+        assert!(is_difficult_clear(4, TSpin::None));
+        assert!(is_difficult_clear(1, TSpin::Mini));
+        assert!(!is_difficult_clear(3, TSpin::None));
+        assert!(!is_difficult_clear(0, TSpin::Full));
+    }
+}
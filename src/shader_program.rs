@@ -1,22 +1,41 @@
 use glium::{Display, Program};
 
+const VERTEX_SHADER_SRC: &str = r#"
+    #version 140
+
+    in vec3 position;
+    in vec2 texture;
+
+    out vec2 v_tex_coords;
+
+    uniform mat4 matrix;
+
+    void main() {
+        v_tex_coords = texture;
+        gl_Position = matrix * vec4(position, 1.0);
+    }
+"#;
+
 pub fn get_shader_program(display: &Display) -> Program {
-    let vertex_shader_src = r#"
+    let fragment_shader_src = r#"
         #version 140
 
-        in vec3 position;
-        in vec2 texture;
+        in vec2 v_tex_coords;
 
-        out vec2 v_tex_coords;
+        out vec4 color;
 
-        uniform mat4 matrix;
+        uniform sampler2D tex;
 
         void main() {
-            v_tex_coords = texture;
-            gl_Position = matrix * vec4(position, 1.0);
+            color = texture(tex, v_tex_coords);
         }
     "#;
 
+    Program::from_source(display, VERTEX_SHADER_SRC, fragment_shader_src, None).unwrap()
+}
+
+// Same texture, but mostly transparent. Needs alpha blending to be enabled.
+pub fn get_ghost_shader_program(display: &Display) -> Program {
     let fragment_shader_src = r#"
         #version 140
 
@@ -28,8 +47,9 @@ pub fn get_shader_program(display: &Display) -> Program {
 
         void main() {
             color = texture(tex, v_tex_coords);
+            color.a *= 0.3;
         }
     "#;
 
-    Program::from_source(display, vertex_shader_src, fragment_shader_src, None).unwrap()
+    Program::from_source(display, VERTEX_SHADER_SRC, fragment_shader_src, None).unwrap()
 }
\ No newline at end of file
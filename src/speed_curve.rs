@@ -0,0 +1,233 @@
+use std::fs;
+use std::io;
+use crate::text_file::{parse_value, invalid_data};
+
+// 20G and faster gravity puts figure on the ground in the same tick, whatever the field height
+pub const INSTANT_GRAVITY: f64 = 20.0;
+
+// Speed of the game on some level. All delays are in ticks.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LevelSpeed {
+    pub level: u32, // first level this speed is used on
+    pub gravity: f64, // in G: rows per tick, 1/60 G is one row per second
+    pub are: u32, // entry delay: pause between lock and spawn of the next figure
+    pub line_clear_delay: u32, // added to ARE when lock cleared lines
+    pub lock_delay: u32, // ticks figure can stay on the ground before locking
+}
+
+// Table of speeds by level. Every row works until the level of the next one,
+// the last row works forever.
+#[derive(Clone, Debug)]
+pub struct SpeedCurve {
+    pub name: String,
+    pub levels: Vec<LevelSpeed>, // sorted by level, never empty
+}
+
+impl SpeedCurve {
+    pub fn get_speed(&self, level: u32) -> LevelSpeed {
+        *self.levels.iter()
+            .rev()
+            .find(|speed| speed.level <= level)
+            .unwrap_or(&self.levels[0])
+    }
+
+    // Plain text, one level per line: "level gravity are line_clear_delay lock_delay".
+    // Name goes to the "name=..." line, lines starting with # are comments.
+    pub fn load(path: &str) -> io::Result<SpeedCurve> {
+        SpeedCurve::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(content: &str) -> io::Result<SpeedCurve> {
+        let mut curve = SpeedCurve {
+            name: String::from("Custom"),
+            levels: vec![],
+        };
+
+        for line in content.lines().map(|line| line.trim()) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(name) = line.strip_prefix("name=") {
+                curve.name = String::from(name.trim());
+                continue;
+            }
+
+            let values: Vec<&str> = line.split_whitespace().collect();
+            if values.len() != 5 {
+                return Err(invalid_data(format!("expected 5 values, got \"{}\"", line)));
+            }
+
+            let gravity: f64 = parse_value(values[1], line)?;
+            // figure would never fall with such gravity
+            if !gravity.is_finite() || gravity < 0.0 {
+                return Err(invalid_data(format!("gravity \"{}\" is not a non-negative number in \"{}\"", values[1], line)));
+            }
+
+            curve.levels.push(LevelSpeed {
+                level: parse_value(values[0], line)?,
+                gravity,
+                are: parse_value(values[2], line)?,
+                line_clear_delay: parse_value(values[3], line)?,
+                lock_delay: parse_value(values[4], line)?,
+            });
+        }
+
+        if curve.levels.is_empty() {
+            return Err(invalid_data(String::from("no levels")));
+        }
+        curve.levels.sort_by_key(|speed| speed.level);
+
+        Ok(curve)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SpeedCurveType {
+    Guideline, // (0.8 - (level - 1) * 0.007) ^ (level - 1) seconds per row, no ARE
+    Nes, // NTSC NES, locks on touch
+    GameBoy, // original Game Boy, locks on touch
+    Tgm, // first TGM, every level is a section of 100 TGM levels
+    Master, // TGM2 Master: ramps up to 20G, then ARE and lock delay get shorter
+}
+
+impl SpeedCurveType {
+    pub fn create(&self) -> SpeedCurve {
+        match self {
+            SpeedCurveType::Guideline => SpeedCurve {
+                name: String::from("Guideline"),
+                levels: (1..=20).map(|level| LevelSpeed {
+                    level,
+                    gravity: get_guideline_gravity(level),
+                    are: 0,
+                    line_clear_delay: 0,
+                    lock_delay: 30,
+                }).collect(),
+            },
+            // NES level 0 is level 1 here
+            SpeedCurveType::Nes => from_frames_per_row("NES",
+                &[48, 43, 38, 33, 28, 23, 18, 13, 8, 6, 5, 5, 5, 4, 4, 4, 3, 3, 3, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2, 1],
+                10, 18),
+            SpeedCurveType::GameBoy => from_frames_per_row("Game Boy",
+                &[53, 49, 45, 41, 37, 33, 28, 22, 17, 11, 10, 9, 8, 7, 6, 6, 5, 5, 4, 4, 3],
+                2, 90),
+            // Gravity in TGM is counted in 1/256 G
+            SpeedCurveType::Tgm => SpeedCurve {
+                name: String::from("TGM"),
+                levels: [(1, 4), (2, 80), (3, 4), (4, 512), (5, 1280), (6, 5120)].iter()
+                    .map(|(level, gravity)| LevelSpeed {
+                        level: *level,
+                        gravity: *gravity as f64 / 256.0,
+                        are: 30,
+                        line_clear_delay: 41,
+                        lock_delay: 30,
+                    }).collect(),
+            },
+            // Gravity in 1/256 G, then ARE, line clear delay and lock delay.
+            // Levels roughly follow TGM2 Master sections, 20G comes at level 500 there.
+            SpeedCurveType::Master => SpeedCurve {
+                name: String::from("Master"),
+                levels: [
+                    (1, 4, 25, 40, 30),
+                    (2, 32, 25, 40, 30),
+                    (3, 80, 25, 40, 30),
+                    (4, 144, 25, 40, 30),
+                    (5, 256, 25, 40, 30),
+                    (6, 768, 25, 40, 30),
+                    (7, 1280, 25, 40, 30),
+                    (8, 5120, 25, 25, 30),
+                    (9, 5120, 25, 16, 30),
+                    (10, 5120, 16, 12, 30),
+                    (11, 5120, 12, 6, 30),
+                    (12, 5120, 12, 6, 17),
+                ].iter().map(|(level, gravity, are, line_clear_delay, lock_delay)| LevelSpeed {
+                    level: *level,
+                    gravity: *gravity as f64 / 256.0,
+                    are: *are,
+                    line_clear_delay: *line_clear_delay,
+                    lock_delay: *lock_delay,
+                }).collect(),
+            },
+        }
+    }
+
+    pub fn next(&self) -> SpeedCurveType {
+        match self {
+            SpeedCurveType::Guideline => SpeedCurveType::Nes,
+            SpeedCurveType::Nes => SpeedCurveType::GameBoy,
+            SpeedCurveType::GameBoy => SpeedCurveType::Tgm,
+            SpeedCurveType::Tgm => SpeedCurveType::Master,
+            SpeedCurveType::Master => SpeedCurveType::Guideline,
+        }
+    }
+}
+
+fn get_guideline_gravity(level: u32) -> f64 {
+    let seconds_per_row = (0.8 - (level as f64 - 1.0) * 0.007).powi(level as i32 - 1);
+    1.0 / (seconds_per_row * 60.0)
+}
+
+// Old games count gravity in frames per row and lock figure as soon as it touches the ground
+fn from_frames_per_row(name: &str, frames_per_row: &[u32], are: u32, line_clear_delay: u32) -> SpeedCurve {
+    SpeedCurve {
+        name: String::from(name),
+        levels: frames_per_row.iter().enumerate().map(|(index, frames)| LevelSpeed {
+            level: index as u32 + 1,
+            gravity: 1.0 / *frames as f64,
+            are,
+            line_clear_delay,
+            lock_delay: 0,
+        }).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn levels_are_sorted() {
+        let curve = SpeedCurve::parse("name=Test\n# comment\n10 1 2 3 4\n\n1 0.5 0 0 30\n5 20 1 1 10").unwrap();
+        assert_eq!(curve.name, "Test");
+        assert_eq!(curve.levels.iter().map(|speed| speed.level).collect::<Vec<u32>>(), vec![1, 5, 10]);
+        assert_eq!(curve.levels[2], LevelSpeed {level: 10, gravity: 1.0, are: 2, line_clear_delay: 3, lock_delay: 4});
+    }
+
+    #[test]
+    fn speed_of_level() {
+        let curve = SpeedCurve::parse("3 0.5 0 0 30\n5 20 1 1 10").unwrap();
+        // levels before the first row use the first row
+        assert_eq!(curve.get_speed(1).level, 3);
+        assert_eq!(curve.get_speed(4).level, 3);
+        assert_eq!(curve.get_speed(5).level, 5);
+        assert_eq!(curve.get_speed(100).level, 5);
+    }
+
+    #[test]
+    fn errors() {
+        let error = SpeedCurve::parse("1 0.5 0 0").unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(error.to_string(), "expected 5 values, got \"1 0.5 0 0\"");
+        assert_eq!(SpeedCurve::parse("1 0.5 0 0 30 1").unwrap_err().to_string(), "expected 5 values, got \"1 0.5 0 0 30 1\"");
+        assert_eq!(SpeedCurve::parse("1 fast 0 0 30").unwrap_err().to_string(), "can't parse \"fast\" in \"1 fast 0 0 30\"");
+        assert_eq!(SpeedCurve::parse("1 nan 0 0 30").unwrap_err().to_string(), "gravity \"nan\" is not a non-negative number in \"1 nan 0 0 30\"");
+        assert_eq!(SpeedCurve::parse("1 inf 0 0 30").unwrap_err().to_string(), "gravity \"inf\" is not a non-negative number in \"1 inf 0 0 30\"");
+        assert_eq!(SpeedCurve::parse("1 -1 0 0 30").unwrap_err().to_string(), "gravity \"-1\" is not a non-negative number in \"1 -1 0 0 30\"");
+        assert_eq!(SpeedCurve::parse("name=Empty").unwrap_err().to_string(), "no levels");
+    }
+
+    #[test]
+    fn built_in_curves() {
+        let mut curve_type = SpeedCurveType::Guideline;
+        loop {
+            let curve = curve_type.create();
+            assert!(!curve.levels.is_empty());
+            assert!(curve.levels.windows(2).all(|pair| pair[0].level < pair[1].level));
+            curve_type = curve_type.next();
+            if curve_type == SpeedCurveType::Guideline {
+                break;
+            }
+        }
+        assert!(SpeedCurveType::Master.create().get_speed(100).gravity >= INSTANT_GRAVITY);
+    }
+}
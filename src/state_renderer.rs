@@ -1,50 +1,97 @@
-use crate::game_state::{GameState, FIELD_WIDTH, FIELD_HEIGHT};
-use glium::{Surface, Display, Frame, VertexBuffer, IndexBuffer};
+use rustris::game_state::{GameState, GameOverReason};
+use glium::{Surface, Display, Frame, VertexBuffer, IndexBuffer, DrawParameters, Blend};
 use nalgebra_glm as glm;
 use glium_text_nxt::FontTexture;
-use crate::tetronimoe::{BlockType, Point};
+use rustris::tetronimoe::{BlockType, Direction, Point};
+use rustris::tspin::TSpin;
+use rustris::events::GameEvent;
+use rustris::game_settings::SPAWN_ROWS;
+use rustris::timestep::TICKS_PER_SECOND;
 use crate::texture_bag::TextureBag;
-use crate::shader_program::get_shader_program;
+use crate::shader_program::{get_shader_program, get_ghost_shader_program};
 use glium::index::PrimitiveType::TrianglesList;
 use crate::vertex::Vertex;
-use std::ops::Deref;
-use glium::texture::RawImage2d;
 use nalgebra_glm::TVec3;
 
 pub const CUP_COORDINATES_START_X: f32 = -0.6;
 pub const CUP_COORDINATES_START_Y: f32 = 0.85;
+// How long big banners stay on the screen
+pub const BANNER_FRAMES: u32 = 120;
 
-pub fn render_sate(state: &GameState, display: &Display, target: &mut Frame, font: &FontTexture, textures: &TextureBag) {
-    render_next_figure(state, display, target, font, textures);
+// Short-lived things shown on top of the field, driven by game events
+pub struct Banners {
+    pub all_clear_frames_left: u32,
+}
+
+impl Banners {
+    pub fn init() -> Banners {
+        Banners {
+            all_clear_frames_left: 0,
+        }
+    }
+
+    pub fn handle_event(&mut self, event: &GameEvent) {
+        if let GameEvent::PerfectClear {..} = event {
+            self.all_clear_frames_left = BANNER_FRAMES;
+        }
+    }
+
+    pub fn update(&mut self) {
+        self.all_clear_frames_left = self.all_clear_frames_left.saturating_sub(1);
+    }
+}
+
+pub fn render_sate(state: &GameState, banners: &Banners, display: &Display, target: &mut Frame, font: &FontTexture, textures: &TextureBag) {
+    render_hold_figure(state, display, target, font, textures);
+    render_next_figures(state, display, target, font, textures);
     render_bucket(state, display, target, textures);
     render_controls_and_score(state, display, target, font);
-    if state.is_paused {
-        render_pause(display, target, font);
+    if let Some(result) = state.get_result() {
+        let banner = match result.reason {
+            GameOverReason::Completed => "COMPLETE",
+            GameOverReason::TimeUp => "TIME UP",
+            _ => "GAME OVER",
+        };
+        render_banner(banner, display, target, font);
+        render_text(String::from("Press Enter to play again"), glm::vec3(-0.45, -0.3, 0.0), display, target, font);
+    } else if state.is_paused {
+        render_banner("PAUSE", display, target, font);
+    } else if banners.all_clear_frames_left > 0 {
+        render_banner("ALL CLEAR", display, target, font);
     }
 }
 
-fn render_next_figure(state: &GameState, display: &Display, target: &mut Frame, font: &FontTexture, textures: &TextureBag) {
-    let text_system = glium_text_nxt::TextSystem::new(display);
-    let text = glium_text_nxt::TextDisplay::new(&text_system, font, "Next figure");
-    let mut score_matrix = glm::translate(&glm::identity(), &glm::vec3(-0.95, 0.9, 0.0));
-    score_matrix = glm::scale(&score_matrix, &glm::vec3(0.04, 0.04, 0.04));
-    glium_text_nxt::draw(&text, &text_system, target, score_matrix, (0.0, 0.0, 0.0, 1.0));
-
-    let image = match state.next_figure.block_type {
-        BlockType::Square => &textures.square,
-        BlockType::LinePiece => &textures.line,
-        BlockType::TBlock => &textures.tblock,
-        BlockType::LBlock => &textures.lblock,
-        BlockType::ReverseLBlock => &textures.reversed_lblock,
-        BlockType::Squiggle => &textures.squiggle,
-        BlockType::ReverseSquiggle => &textures.reversed_squiggle,
+// Upcoming figures are stacked from top to bottom under the hold panel
+fn render_next_figures(state: &GameState, display: &Display, target: &mut Frame, font: &FontTexture, textures: &TextureBag) {
+    render_text(String::from("Next"), glm::vec3(-0.95, 0.6, 0.0), display, target, font);
+    for (index, block_type) in state.peek_next().into_iter().enumerate() {
+        render_figure_image(state, block_type, 0.55 - index as f32 * 0.15, display, target, textures);
+    }
+}
+
+fn render_hold_figure(state: &GameState, display: &Display, target: &mut Frame, font: &FontTexture, textures: &TextureBag) {
+    render_text(String::from("Hold"), glm::vec3(-0.95, 0.9, 0.0), display, target, font);
+    if let Some(figure) = &state.hold_figure {
+        render_figure_image(state, figure.block_type, 0.85, display, target, textures);
+    }
+}
+
+// Draws figure picture left to the bucket, top_y is the top edge of the picture
+fn render_figure_image(state: &GameState, block_type: BlockType, top_y: f32, display: &Display, target: &mut Frame, textures: &TextureBag) {
+    let image = match get_figure_texture(block_type, textures) {
+        Some(image) => image,
+        None => {
+            let offsets = state.rotation_system.get_tiles_offsets(block_type, Direction::Up);
+            render_custom_figure_image(offsets, top_y, display, target, textures);
+            return;
+        },
     };
 
     let shape = vec![
-        Vertex {position: [-0.95, 0.85, 0.0], texture: [0.0, 1.0]}, // top-left
-        Vertex {position: [-0.75, 0.85, 0.0], texture: [1.0, 1.0]}, // top-right
-        Vertex {position: [-0.75, 0.7, 0.0], texture: [1.0, 0.0]}, // bottom-right
-        Vertex {position: [-0.95, 0.7, 0.0], texture: [0.0, 0.0]}, // bottom-left
+        Vertex {position: [-0.93, top_y, 0.0], texture: [0.0, 1.0]}, // top-left
+        Vertex {position: [-0.77, top_y, 0.0], texture: [1.0, 1.0]}, // top-right
+        Vertex {position: [-0.77, top_y - 0.13, 0.0], texture: [1.0, 0.0]}, // bottom-right
+        Vertex {position: [-0.93, top_y - 0.13, 0.0], texture: [0.0, 0.0]}, // bottom-left
     ];
 
     let indices: [u16; 6] = [
@@ -68,13 +115,80 @@ fn render_next_figure(state: &GameState, display: &Display, target: &mut Frame,
         .unwrap();
 }
 
+// Custom figures have no pictures
+fn get_figure_texture(block_type: BlockType, textures: &TextureBag) -> Option<&glium::Texture2d> {
+    match block_type {
+        BlockType::Square => Some(&textures.square),
+        BlockType::LinePiece => Some(&textures.line),
+        BlockType::TBlock => Some(&textures.tblock),
+        BlockType::LBlock => Some(&textures.lblock),
+        BlockType::ReverseLBlock => Some(&textures.reversed_lblock),
+        BlockType::Squiggle => Some(&textures.squiggle),
+        BlockType::ReverseSquiggle => Some(&textures.reversed_squiggle),
+        BlockType::Custom(_) => None,
+    }
+}
+
+// Figure is drawn block by block into the same place the picture takes
+fn render_custom_figure_image(offsets: Vec<(isize, isize)>, top_y: f32, display: &Display, target: &mut Frame, textures: &TextureBag) {
+    let min_x = offsets.iter().map(|(x, _)| *x).min().unwrap();
+    let max_x = offsets.iter().map(|(x, _)| *x).max().unwrap();
+    let min_y = offsets.iter().map(|(_, y)| *y).min().unwrap();
+    let max_y = offsets.iter().map(|(_, y)| *y).max().unwrap();
+    // blocks keep the shape of the bucket blocks
+    let (block_width, block_height) = get_point_size(10, 10 + SPAWN_ROWS);
+    let scale = (0.16 / ((max_x - min_x + 1) as f32 * block_width)).min(0.13 / ((max_y - min_y + 1) as f32 * block_height));
+    let (point_width, point_height) = (block_width * scale, block_height * scale);
+
+    let indices: [u16; 6] = [
+        0, 1, 2,
+        0, 3, 2
+    ];
+
+    let uniforms = uniform! {
+        matrix: [
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0f32],
+        ],
+        tex: &textures.block,
+    };
+
+    let index_buffer = IndexBuffer::new(display, TrianglesList, &indices).unwrap();
+    for (x, y) in offsets {
+        let point_position_x = -0.93 + (x - min_x) as f32 * point_width;
+        let point_position_y = top_y - (y - min_y) as f32 * point_height;
+        let point_shape = vec![
+            Vertex {position: [point_position_x, point_position_y, 0.0], texture: [0.0, 1.0]}, // top-left
+            Vertex {position: [point_position_x + point_width, point_position_y, 0.0], texture: [1.0, 1.0]}, // top-right
+            Vertex {position: [point_position_x + point_width, point_position_y - point_height, 0.0], texture: [1.0, 0.0]}, // bottom-right
+            Vertex {position: [point_position_x, point_position_y - point_height, 0.0], texture: [0.0, 0.0]}, // bottom-left
+        ];
+
+        let vertex_buffer = VertexBuffer::new(display, &point_shape).unwrap();
+        target.draw(&vertex_buffer, &index_buffer, &get_shader_program(display), &uniforms, &Default::default())
+            .unwrap();
+    }
+}
+
 fn render_bucket(state: &GameState, display: &Display, target: &mut Frame, textures: &TextureBag) {
-    // render bucket itself
+    // Visible field is shown in the bucket and the spawn area right above it,
+    // the rest of the hidden buffer is not shown at all
+    let field_width = state.field.width;
+    let field_height = state.settings.get_field_height();
+    let first_shown_row = state.settings.get_spawn_row() as isize;
+    let point_size = get_point_size(field_width, field_height + SPAWN_ROWS);
+
+    // render bucket itself, it is as big as the visible field
+    let right_x = CUP_COORDINATES_START_X + field_width as f32 * point_size.0;
+    let top_y = CUP_COORDINATES_START_Y - SPAWN_ROWS as f32 * point_size.1;
+    let bottom_y = top_y - field_height as f32 * point_size.1;
     let bucket_shape = vec![
-        Vertex {position: [-0.6, 0.85, 0.0], texture: [0.0, 1.0]}, // top-left
-        Vertex {position: [0.45, 0.85, 0.0], texture: [1.0, 1.0]}, // top-right
-        Vertex {position: [0.45, -0.85, 0.0], texture: [1.0, 0.0]}, // bottom-right
-        Vertex {position: [-0.6, -0.85, 0.0], texture: [0.0, 0.0]}, // bottom-left
+        Vertex {position: [CUP_COORDINATES_START_X, top_y, 0.0], texture: [0.0, 1.0]}, // top-left
+        Vertex {position: [right_x, top_y, 0.0], texture: [1.0, 1.0]}, // top-right
+        Vertex {position: [right_x, bottom_y, 0.0], texture: [1.0, 0.0]}, // bottom-right
+        Vertex {position: [CUP_COORDINATES_START_X, bottom_y, 0.0], texture: [0.0, 0.0]}, // bottom-left
     ];
 
     let indices: [u16; 6] = [
@@ -97,20 +211,44 @@ fn render_bucket(state: &GameState, display: &Display, target: &mut Frame, textu
     target.draw(&vertex_buffer, &index_buffer, &get_shader_program(display), &uniforms, &Default::default())
         .unwrap();
 
+    let shown_points = |points: Vec<Point>| points.into_iter()
+        .filter(|point| point.y >= first_shown_row)
+        .map(|point| Point {x: point.x, y: point.y - first_shown_row})
+        .collect::<Vec<Point>>();
+
     // render filled space
-    for point in state.filled_space.clone() {
-        render_point(point, display, target, textures);
+    for point in shown_points(state.field.get_filled_points()) {
+        render_point(point, point_size, false, display, target, textures);
+    }
+
+    // next figure is not in play during ARE
+    if state.entry_delay_left > 0 {
+        return;
+    }
+
+    // render ghost: shows where current figure will land
+    if state.settings.show_ghost {
+        for point in shown_points(state.get_landing_position().tiles) {
+            render_point(point, point_size, true, display, target, textures);
+        }
     }
 
     // render current figure
-    for point in state.current_figure.tiles.clone() {
-        render_point(point, display, target, textures);
+    for point in shown_points(state.current_figure.get_tiles()) {
+        render_point(point, point_size, false, display, target, textures);
     }
 }
 
-fn render_point(point: Point, display: &Display, target: &mut Frame, textures: &TextureBag) {
-    let point_width: f32 = 1.05 / FIELD_WIDTH as f32;
-    let point_height: f32 = 1.7 / FIELD_HEIGHT as f32;
+// Blocks keep square shape of the standard 10x20 field with the spawn area,
+// bigger fields get smaller blocks so they still fit into the bucket
+fn get_point_size(field_width: usize, field_height: usize) -> (f32, f32) {
+    let scale = (10.0 / field_width as f32).min(22.0 / field_height as f32);
+    (1.05 / 10.0 * scale, 1.7 / 22.0 * scale)
+}
+
+// Ghost points are drawn translucent
+fn render_point(point: Point, point_size: (f32, f32), is_ghost: bool, display: &Display, target: &mut Frame, textures: &TextureBag) {
+    let (point_width, point_height) = point_size;
 
     let point_position_x: f32 = CUP_COORDINATES_START_X + point.x as f32 * point_width;
     let point_position_y: f32 = CUP_COORDINATES_START_Y - point.y as f32 * point_height;
@@ -139,48 +277,119 @@ fn render_point(point: Point, display: &Display, target: &mut Frame, textures: &
 
     let vertex_buffer = VertexBuffer::new(display, &point_shape).unwrap();
     let index_buffer = IndexBuffer::new(display, TrianglesList, &indices).unwrap();
-    target.draw(&vertex_buffer, &index_buffer, &get_shader_program(display), &uniforms, &Default::default())
-        .unwrap();
+    if is_ghost {
+        let draw_parameters = DrawParameters {
+            blend: Blend::alpha_blending(),
+            ..Default::default()
+        };
+        target.draw(&vertex_buffer, &index_buffer, &get_ghost_shader_program(display), &uniforms, &draw_parameters)
+            .unwrap();
+    } else {
+        target.draw(&vertex_buffer, &index_buffer, &get_shader_program(display), &uniforms, &Default::default())
+            .unwrap();
+    }
 }
 
+// Right column: score, current rules and controls, one line under another
 fn render_controls_and_score(state: &GameState, display: &Display, target: &mut Frame, font: &FontTexture) {
-    let score_text = format!("Score: {}", state.current_score);
-    render_text(score_text, glm::vec3(0.5, 0.9, 0.0), display, target, font);
-
-    let best_text = format!("Best: {}", state.best_score);
-    render_text(best_text, glm::vec3(0.5, 0.8, 0.0), display, target, font);
-
-    let level_text = format!("Level: {}", state.level);
-    render_text(level_text, glm::vec3(0.5, 0.7, 0.0), display, target, font);
-
-    let controls_header = String::from("Controls:");
-    render_text(controls_header, glm::vec3(0.5, 0.4, 0.0), display, target, font);
+    let seed_text = format!("Seed: {}", state.seed);
+    render_text(seed_text, glm::vec3(-0.95, -0.5, 0.0), display, target, font);
+
+    render_last_lock_result(state, display, target, font);
+
+    let lines = vec![
+        format!("Score: {}", state.current_score),
+        format!("Best: {}", state.best_score),
+        format!("Mode: {}", state.game_mode.get_name()),
+        format!("Level: {}", state.level),
+        if state.game_mode.has_dig_garbage() {
+            format!("Garbage left: {}", state.garbage_rows_left)
+        } else {
+            format!("Lines: {}", state.lines_cleared)
+        },
+        format!("Pieces: {}", state.pieces_locked),
+        format!("Time: {}", format_ticks(state.get_timer())),
+        format!("Rotation: {}", state.rotation_system.get_name()),
+        format!("Randomizer: {}", state.randomizer.get_name()),
+        format!("Speed: {}", state.speed_curve.name),
+        format!("Piece set: {}", state.piece_set.name),
+        String::from("Controls:"),
+        String::from("Left: Num4"),
+        String::from("Right: Num6"),
+        String::from("CCW: Num3|7"),
+        String::from("CW: Num1|5|9"),
+        String::from("Hard drop: Num8"),
+        String::from("Soft drop: Num2"),
+        String::from("Hold: Num0"),
+        String::from("Pause: Esc|F1"),
+        String::from("Change rotation: F2"),
+        String::from("Change randomizer: F3"),
+        String::from("Replay last game: F4"),
+        String::from("Previews count: F5"),
+        String::from("Ghost on/off: F6"),
+        String::from("Change speed: F7"),
+        String::from("Change mode: F8"),
+        String::from("Change pieces: F9"),
+        String::from("New game: Enter"),
+    ];
 
-    let controls_left_text = String::from("Left: Num4");
-    render_text(controls_left_text, glm::vec3(0.5, 0.3, 0.0), display, target, font);
+    // lines fill the column from 0.9 to -0.9
+    let line_height = 1.8 / (lines.len() - 1) as f32;
+    for (index, line) in lines.into_iter().enumerate() {
+        render_text(line, glm::vec3(0.5, 0.9 - index as f32 * line_height, 0.0), display, target, font);
+    }
+}
 
-    let controls_right_text = String::from("Right: Num6");
-    render_text(controls_right_text, glm::vec3(0.5, 0.2, 0.0), display, target, font);
+// m:ss.cc
+fn format_ticks(ticks: u64) -> String {
+    let ticks_per_second = TICKS_PER_SECOND as u64;
+    let seconds = ticks / ticks_per_second;
+    let hundredths = ticks % ticks_per_second * 100 / ticks_per_second;
+    format!("{}:{:02}.{:02}", seconds / 60, seconds % 60, hundredths)
+}
 
-    let controls_ccw_text = String::from("CCW: Num3|7");
-    render_text(controls_ccw_text, glm::vec3(0.5, 0.1, 0.0), display, target, font);
+// Shows special things about the last lock (T-spins, tetrises, combos), until the next figure is locked
+fn render_last_lock_result(state: &GameState, display: &Display, target: &mut Frame, font: &FontTexture) {
+    let lock_result = match state.last_lock_result {
+        Some(lock_result) => lock_result,
+        None => return,
+    };
 
-    let controls_cw_text = String::from("CW: Num1|5|9");
-    render_text(controls_cw_text, glm::vec3(0.5, 0.0, 0.0), display, target, font);
+    let tspin_text = match lock_result.tspin {
+        TSpin::None => None,
+        TSpin::Mini => Some("T-Spin Mini"),
+        TSpin::Full => Some("T-Spin"),
+    };
+    if let Some(tspin_text) = tspin_text {
+        render_text(String::from(tspin_text), glm::vec3(-0.95, -0.6, 0.0), display, target, font);
+    }
 
-    let controls_hard_drop_text = String::from("Hard drop: Num8");
-    render_text(controls_hard_drop_text, glm::vec3(0.5, -0.1, 0.0), display, target, font);
+    // usual clears are not that special
+    let lines_text = match (lock_result.tspin, lock_result.cleared_lines_count) {
+        (_, 0) => None,
+        (TSpin::None, 1) | (TSpin::None, 2) | (TSpin::None, 3) => None,
+        (_, 1) => Some("Single"),
+        (_, 2) => Some("Double"),
+        (_, 3) => Some("Triple"),
+        _ => Some("Tetris"),
+    };
+    if let Some(lines_text) = lines_text {
+        render_text(String::from(lines_text), glm::vec3(-0.95, -0.7, 0.0), display, target, font);
+    }
 
-    let controls_soft_drop_text = String::from("Soft drop: Num2");
-    render_text(controls_soft_drop_text, glm::vec3(0.5, -0.2, 0.0), display, target, font);
+    if lock_result.cleared_lines_count > 0 && lock_result.combo > 0 {
+        render_text(format!("Combo {}", lock_result.combo), glm::vec3(-0.95, -0.8, 0.0), display, target, font);
+    }
 
-    let controls_pause_text = String::from("Pause: Esc|F1");
-    render_text(controls_pause_text, glm::vec3(0.5, -0.3, 0.0), display, target, font);
+    if lock_result.is_back_to_back {
+        render_text(String::from("Back-to-back"), glm::vec3(-0.95, -0.9, 0.0), display, target, font);
+    }
 }
 
-fn render_pause(display: &Display, target: &mut Frame, font: &FontTexture) {
+// Big text across the field
+fn render_banner(banner: &str, display: &Display, target: &mut Frame, font: &FontTexture) {
     let text_system = glium_text_nxt::TextSystem::new(display);
-    let text = glium_text_nxt::TextDisplay::new(&text_system, font, "PAUSE");
+    let text = glium_text_nxt::TextDisplay::new(&text_system, font, banner);
     let scale_ratio = 2.0 * 0.7 / text.get_width();
     let mut text_matrix = glm::translate(&glm::identity(), &glm::vec3(-0.7, -0.1, 0.0));
     text_matrix = glm::scale(&text_matrix, &glm::vec3(scale_ratio, scale_ratio, scale_ratio));
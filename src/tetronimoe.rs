@@ -1,7 +1,6 @@
-use rand::random;
-use crate::game_state::FIELD_WIDTH;
+use crate::rotation_system::RotationSystem;
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum Direction {
     Up,
     Down,
@@ -12,7 +11,7 @@ pub enum Direction {
 
 impl Direction {
     pub fn next_ccw(current: Direction) -> Direction {
-        return match current {
+        match current {
             Direction::Up => Direction::Left,
             Direction::Right => Direction::Up,
             Direction::Down => Direction::Right,
@@ -22,7 +21,7 @@ impl Direction {
     }
 
     pub fn next_cw(current: Direction) -> Direction {
-        return match current {
+        match current {
             Direction::Up => Direction::Right,
             Direction::Right => Direction::Down,
             Direction::Down => Direction::Left,
@@ -32,7 +31,7 @@ impl Direction {
     }
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum BlockType {
     Square,
     Squiggle,
@@ -40,9 +39,20 @@ pub enum BlockType {
     LBlock,
     ReverseLBlock,
     TBlock,
-    LinePiece
+    LinePiece,
+    Custom(usize), // figure from the piece set, see piece_set::PieceSet::shapes
 }
 
+pub const BLOCK_TYPES: [BlockType; 7] = [
+    BlockType::Square,
+    BlockType::Squiggle,
+    BlockType::ReverseSquiggle,
+    BlockType::LBlock,
+    BlockType::ReverseLBlock,
+    BlockType::TBlock,
+    BlockType::LinePiece,
+];
+
 #[derive(Copy, Clone, Debug)]
 pub struct Point {
     pub x: isize,
@@ -54,505 +64,67 @@ pub struct Figure {
     pub block_type: BlockType,
     pub direction: Direction,
     pub center: Point, // center is basically center of rotation for the figure.
-    // It is asymmetrical, but full circle CW/CCW rotation will held figure intact
-    // Center position depends on figure type:
-    //   * Square - left lower block, just because
-    //   * Squiggle - lower central block(i.e. right square in lower row)
-    //   * ReverseSquiggle - lower central block (i.e. left square in lower row)
-    //   * LBlock - block connecting | and _
-    //   * ReverseLBlock - block connecting | and _
-    //   * TBlock - block connecting | and _
-    //   * LinePiece - second block from below
+    // Where exactly it is depends on the rotation system, see RotationSystem::get_tiles_offsets
     pub tiles: Vec<Point>,
 }
 
 impl Figure {
-    pub fn init(block_type: BlockType, direction: Direction, center: Point) -> Figure {
-        let tiles = calculate_tiles_position(&block_type, &direction, center);
+    pub fn init(block_type: BlockType, direction: Direction, center: Point, rotation_system: &dyn RotationSystem) -> Figure {
+        let tiles = calculate_tiles_position(rotation_system.get_tiles_offsets(block_type, direction), center);
         Figure { block_type, direction, center, tiles }
     }
 
-    pub fn init_random() -> Figure {
-        let block_type = match random::<u32>() % 7 {
-            0 => BlockType::Square,
-            1 => BlockType::Squiggle,
-            2 => BlockType::ReverseSquiggle,
-            3 => BlockType::LBlock,
-            4 => BlockType::ReverseLBlock,
-            5 => BlockType::TBlock,
-            6 => BlockType::LinePiece,
-            _ => unimplemented!(),
-        };
-
-        let center = Point {x: (FIELD_WIDTH / 2) as isize, y: 2};
-        let tiles = calculate_tiles_position(&block_type, &Direction::Up, center);
-
-        Figure {
-            block_type,
-            direction: Direction::Up,
-            center,
-            tiles
-        }
+    // Creates figure in spawn orientation and position, spawn_row is the top row of the spawn area
+    pub fn spawn(block_type: BlockType, field_width: usize, spawn_row: usize, rotation_system: &dyn RotationSystem) -> Figure {
+        let start_position = rotation_system.get_start_position(block_type, field_width);
+        let center = Point {x: start_position.x, y: start_position.y + spawn_row as isize};
+        Figure::init(block_type, Direction::Up, center, rotation_system)
     }
 
-    // Note that rotation does not work according to SRS.
-    // Just because I decided to make experiment with it.
-    pub fn rotate_cw(&mut self) {
-        self.direction = Direction::next_cw(self.direction.clone());
-        self.tiles = calculate_tiles_position(&self.block_type, &self.direction, self.center);
+    // Rotation itself does not care about walls and other blocks,
+    // use GameState::rotate_* to get rotation with wall kicks
+    pub fn rotate_cw(&mut self, rotation_system: &dyn RotationSystem) {
+        self.direction = Direction::next_cw(self.direction);
+        self.tiles = calculate_tiles_position(rotation_system.get_tiles_offsets(self.block_type, self.direction), self.center);
     }
 
-    // Note that rotation does not work according to SRS.
-    // Just because I decided to make experiment with it.
-    pub fn rotate_ccw(&mut self) {
-        self.direction = Direction::next_ccw(self.direction.clone());
-        self.tiles = calculate_tiles_position(&self.block_type, &self.direction, self.center);
+    pub fn rotate_ccw(&mut self, rotation_system: &dyn RotationSystem) {
+        self.direction = Direction::next_ccw(self.direction);
+        self.tiles = calculate_tiles_position(rotation_system.get_tiles_offsets(self.block_type, self.direction), self.center);
     }
 
     pub fn get_tiles(&self) -> Vec<Point> {
         self.tiles.clone()
     }
 
+    pub fn shift(&mut self, x: isize, y: isize) {
+        self.center.x += x;
+        self.center.y += y;
+        for tile in self.tiles.iter_mut() {
+            tile.x += x;
+            tile.y += y;
+        }
+    }
+
     pub fn shift_right(&mut self) {
-        self.center.x += 1;
-        self.tiles = calculate_tiles_position(&self.block_type, &self.direction, self.center);
+        self.shift(1, 0);
     }
 
     pub fn shift_left(&mut self) {
-        self.center.x -= 1;
-        self.tiles = calculate_tiles_position(&self.block_type, &self.direction, self.center);
+        self.shift(-1, 0);
     }
 
     pub fn shift_down(&mut self) {
-        self.center.y += 1;
-        self.tiles = calculate_tiles_position(&self.block_type, &self.direction, self.center);
+        self.shift(0, 1);
     }
 
-    pub fn get_start_position() -> Point {
-        Point {x: (FIELD_WIDTH / 2) as isize, y: 2}
+    pub fn shift_up(&mut self) {
+        self.shift(0, -1);
     }
 }
 
-fn calculate_tiles_position(block_type: &BlockType, direction: &Direction, center: Point) -> Vec<Point> {
-    return match block_type {
-        BlockType::Square => {
-            vec![
-                center.clone(), // lower left
-                Point {
-                    x: center.x,
-                    y: center.y - 1
-                }, // higher left
-                Point {
-                    x: center.x + 1,
-                    y: center.y - 1
-                }, // higher right
-                Point {
-                    x: center.x + 1,
-                    y: center.y
-                }, // lower right
-            ]
-        },
-        BlockType::Squiggle => get_squiggle_tiles(&direction, center),
-        BlockType::ReverseSquiggle => get_reverse_squiggle_tiles(&direction, center),
-        BlockType::LBlock => get_l_block_tiles(&direction, center),
-        BlockType::ReverseLBlock => get_reverse_l_block_tiles(&direction, center),
-        BlockType::TBlock => get_t_block_tiles(&direction, center),
-        BlockType::LinePiece => get_line_piece_tiles(&direction, center),
-    };
-}
-
-fn get_squiggle_tiles(direction: &Direction, center: Point) -> Vec<Point> {
-    return match direction {
-        Direction::Up => vec![
-            center.clone(),
-            Point {
-                x: center.x,
-                y: center.y + 1
-            },// lower left
-            Point {
-                x: center.x - 1,
-                y: center.y - 1
-            },// upper right
-            Point {
-                x: center.x - 1,
-                y: center.y
-            },// upper left
-        ],
-        Direction::Right => vec![
-            center.clone(),
-            Point {
-                x: center.x - 1,
-                y: center.y
-            },// lower left
-            Point {
-                x: center.x + 1,
-                y: center.y - 1
-            },// upper right
-            Point {
-                x: center.x,
-                y: center.y - 1
-            },// upper left
-        ],
-        Direction::Down => vec![
-            center.clone(),
-            Point {
-                x: center.x,
-                y: center.y - 1
-            },// lower left
-            Point {
-                x: center.x + 1,
-                y: center.y + 1
-            },// upper right
-            Point {
-                x: center.x + 1,
-                y: center.y
-            },// upper left
-        ],
-        Direction::Left => vec![
-            center.clone(),
-            Point {
-                x: center.x + 1,
-                y: center.y
-            },// lower left
-            Point {
-                x: center.x - 1,
-                y: center.y + 1
-            },// upper right
-            Point {
-                x: center.x,
-                y: center.y + 1
-            },// upper left
-        ],
-        _ => unreachable!(),
-    }
-}
-
-fn get_reverse_squiggle_tiles(direction: &Direction, center: Point) -> Vec<Point> {
-    return match direction {
-        Direction::Up => vec![
-            center.clone(),
-            Point {
-                x: center.x,
-                y: center.y + 1
-            },// lower right
-            Point {
-                x: center.x + 1,
-                y: center.y
-            },// upper right
-            Point {
-                x: center.x + 1,
-                y: center.y - 1
-            },// upper left
-        ],
-        Direction::Right => vec![
-            center.clone(),
-            Point {
-                x: center.x + 1,
-                y: center.y
-            },// lower right
-            Point {
-                x: center.x,
-                y: center.y - 1
-            },// upper right
-            Point {
-                x: center.x - 1,
-                y: center.y - 1
-            },// upper left
-        ],
-        Direction::Down => vec![
-            center.clone(),
-            Point {
-                x: center.x,
-                y: center.y - 1
-            },// lower right
-            Point {
-                x: center.x - 1,
-                y: center.y
-            },// upper right
-            Point {
-                x: center.x - 1,
-                y: center.y + 1
-            },// upper left
-        ],
-        Direction::Left => vec![
-            center.clone(),
-            Point {
-                x: center.x - 1,
-                y: center.y
-            },// lower right
-            Point {
-                x: center.x,
-                y: center.y + 1
-            },// upper right
-            Point {
-                x: center.x + 1,
-                y: center.y + 1
-            },// upper left
-        ],
-        _ => unreachable!(),
-    }
-}
-
-fn get_l_block_tiles(direction: &Direction, center: Point) -> Vec<Point> {
-    return match direction {
-        Direction::Up => vec![
-            center.clone(),
-            Point {
-                x: center.x + 1,
-                y: center.y
-            },// lower right
-            Point {
-                x: center.x,
-                y: center.y - 1
-            },// block right above
-            Point {
-                x: center.x,
-                y: center.y - 2
-            },// 1 block above
-        ],
-        Direction::Right => vec![
-            center.clone(),
-            Point {
-                x: center.x,
-                y: center.y + 1
-            },// lower right
-            Point {
-                x: center.x + 1,
-                y: center.y
-            },// block right above
-            Point {
-                x: center.x + 2,
-                y: center.y
-            },// 1 block above
-        ],
-        Direction::Down => vec![
-            center.clone(),
-            Point {
-                x: center.x - 1,
-                y: center.y
-            },// lower right
-            Point {
-                x: center.x,
-                y: center.y + 1
-            },// block right above
-            Point {
-                x: center.x,
-                y: center.y + 2
-            },// 1 block above
-        ],
-        Direction::Left => vec![
-            center.clone(),
-            Point {
-                x: center.x,
-                y: center.y - 1
-            },// lower right
-            Point {
-                x: center.x - 1,
-                y: center.y
-            },// block right above
-            Point {
-                x: center.x - 2,
-                y: center.y
-            },// 1 block above
-        ],
-        _ => unreachable!(),
-    }
-}
-
-fn get_reverse_l_block_tiles(direction: &Direction, center: Point) -> Vec<Point> {
-    return match direction {
-        Direction::Up => vec![
-            center.clone(),
-            Point {
-                x: center.x - 1,
-                y: center.y
-            },// lower left
-            Point {
-                x: center.x,
-                y: center.y - 1
-            },// block right above
-            Point {
-                x: center.x,
-                y: center.y - 2
-            },// 1 block above
-        ],
-        Direction::Right => vec![
-            center.clone(),
-            Point {
-                x: center.x,
-                y: center.y - 1
-            },// lower left
-            Point {
-                x: center.x + 1,
-                y: center.y
-            },// block right above
-            Point {
-                x: center.x + 2,
-                y: center.y
-            },// 1 block above
-        ],
-        Direction::Down => vec![
-            center.clone(),
-            Point {
-                x: center.x + 1,
-                y: center.y
-            },// lower left
-            Point {
-                x: center.x,
-                y: center.y + 1
-            },// block right above
-            Point {
-                x: center.x,
-                y: center.y + 2
-            },// 1 block above
-        ],
-        Direction::Left => vec![
-            center.clone(),
-            Point {
-                x: center.x,
-                y: center.y + 1
-            },// lower left
-            Point {
-                x: center.x - 1,
-                y: center.y
-            },// block right above
-            Point {
-                x: center.x - 2,
-                y: center.y
-            },// 1 block above
-        ],
-        _ => unreachable!(),
-    }
-}
-
-fn get_t_block_tiles(direction: &Direction, center: Point) -> Vec<Point> {
-    return match direction {
-        Direction::Up => vec![
-            center.clone(),
-            Point {
-                x: center.x - 1,
-                y: center.y
-            },// lower left
-            Point {
-                x: center.x + 1,
-                y: center.y
-            },// lower right
-            Point {
-                x: center.x,
-                y: center.y - 1
-            },// upper
-        ],
-        Direction::Right => vec![
-            center.clone(),
-            Point {
-                x: center.x,
-                y: center.y - 1
-            },// lower left
-            Point {
-                x: center.x,
-                y: center.y + 1
-            },// lower right
-            Point {
-                x: center.x + 1,
-                y: center.y
-            },// upper
-        ],
-        Direction::Down => vec![
-            center.clone(),
-            Point {
-                x: center.x + 1,
-                y: center.y
-            },// lower left
-            Point {
-                x: center.x - 1,
-                y: center.y
-            },// lower right
-            Point {
-                x: center.x,
-                y: center.y + 1
-            },// upper
-        ],
-        Direction::Left => vec![
-            center.clone(),
-            Point {
-                x: center.x,
-                y: center.y + 1
-            },// lower left
-            Point {
-                x: center.x,
-                y: center.y - 1
-            },// lower right
-            Point {
-                x: center.x - 1,
-                y: center.y
-            },// upper
-        ],
-        _ => unreachable!(),
-    }
-}
-
-fn get_line_piece_tiles(direction: &Direction, center: Point) -> Vec<Point> {
-    return match direction {
-        Direction::Up => vec![
-            center.clone(),
-            Point {
-                x: center.x,
-                y: center.y - 1
-            },// block right above center
-            Point {
-                x: center.x,
-                y: center.y + 1
-            },// 1 block above center
-            Point {
-                x: center.x,
-                y: center.y + 2
-            },// block right below center
-        ],
-        Direction::Right => vec![
-            center.clone(),
-            Point {
-                x: center.x + 1,
-                y: center.y
-            },// block right above center
-            Point {
-                x: center.x - 1,
-                y: center.y
-            },// 1 block above center
-            Point {
-                x: center.x - 2,
-                y: center.y
-            },// block right below center
-        ],
-        Direction::Down => vec![
-            center.clone(),
-            Point {
-                x: center.x,
-                y: center.y + 1
-            },// block right above center
-            Point {
-                x: center.x,
-                y: center.y - 1
-            },// 1 block above center
-            Point {
-                x: center.x,
-                y: center.y - 2
-            },// block right below center
-        ],
-        Direction::Left => vec![
-            center.clone(),
-            Point {
-                x: center.x - 1,
-                y: center.y
-            },// block right above center
-            Point {
-                x: center.x + 1,
-                y: center.y
-            },// 1 block above center
-            Point {
-                x: center.x + 2,
-                y: center.y
-            },// block right below center
-        ],
-        _ => unreachable!(),
-    }
+fn calculate_tiles_position(offsets: Vec<(isize, isize)>, center: Point) -> Vec<Point> {
+    offsets.iter()
+        .map(|(x, y)| Point {x: center.x + x, y: center.y + y})
+        .collect()
 }
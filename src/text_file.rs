@@ -0,0 +1,11 @@
+use std::io;
+
+// Helpers for plain text files of the game: speed curves, piece sets
+
+pub fn parse_value<T: std::str::FromStr>(value: &str, line: &str) -> io::Result<T> {
+    value.parse().map_err(|_| invalid_data(format!("can't parse \"{}\" in \"{}\"", value, line)))
+}
+
+pub fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
@@ -13,8 +13,8 @@ impl Texture {
         }
     }
 
-    pub fn as_raw_image_2d(&self) -> RawImage2d<u8> {
-        return glium::texture::RawImage2d::from_raw_rgba_reversed(&self.image.to_rgba().into_raw(), self.image.dimensions());
+    pub fn as_raw_image_2d(&self) -> RawImage2d<'_, u8> {
+        glium::texture::RawImage2d::from_raw_rgba_reversed(&self.image.to_rgba().into_raw(), self.image.dimensions())
     }
 }
 
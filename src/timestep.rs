@@ -0,0 +1,40 @@
+use std::time::Duration;
+
+// Engine always advances in ticks of 1/60 s, every frame count in the game
+// (gravity, lock delay, DAS, ARR) is a number of ticks
+pub const TICKS_PER_SECOND: u32 = 60;
+// Ticks that can be caught up at once. After a long stall (window dragged, debugger)
+// the rest of the lost time is dropped instead of running the game at full speed.
+pub const MAX_CATCH_UP_TICKS: u32 = 10;
+
+// Turns real time into a number of engine ticks, independent from the render rate.
+// Headless users can just call GameState::update as often as they want.
+pub struct FixedTimestep {
+    accumulator: Duration,
+}
+
+impl FixedTimestep {
+    pub fn init() -> FixedTimestep {
+        FixedTimestep {
+            accumulator: Duration::from_secs(0),
+        }
+    }
+
+    pub fn get_tick_duration() -> Duration {
+        Duration::from_secs(1) / TICKS_PER_SECOND
+    }
+
+    // Adds real time passed since the previous call, returns how many ticks to run now
+    pub fn advance(&mut self, elapsed: Duration) -> u32 {
+        let tick_duration = FixedTimestep::get_tick_duration();
+        self.accumulator += elapsed;
+
+        let mut ticks = 0;
+        while self.accumulator >= tick_duration {
+            self.accumulator -= tick_duration;
+            ticks += 1;
+        }
+
+        ticks.min(MAX_CATCH_UP_TICKS)
+    }
+}
@@ -0,0 +1,133 @@
+use crate::tetronimoe::{Figure, BlockType, Point};
+use crate::collision_checker::cell_is_free;
+use crate::field::Field;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TSpin {
+    None,
+    Mini,
+    Full,
+}
+
+// 3-corner rule: T-block is locked right after a rotation and at least 3 of 4 cells
+// diagonal to its central block are occupied (walls and floor count as occupied).
+// It is a full T-spin if both corners on the pointing side are occupied, otherwise mini.
+// Mini is upgraded to full when rotation needed the far 1x2 kick (like SRS TST kick).
+// Works with any rotation system, central block and pointing side are found from the tiles.
+pub fn detect_tspin(figure: &Figure, last_move_was_rotation: bool, last_rotation_kick: (isize, isize), field: &Field) -> TSpin {
+    if figure.block_type != BlockType::TBlock || !last_move_was_rotation {
+        return TSpin::None;
+    }
+
+    let (center, pointing) = match find_center_and_pointing_side(figure) {
+        Some(result) => result,
+        None => return TSpin::None,
+    };
+
+    let corners = [(-1, -1), (1, -1), (-1, 1), (1, 1)];
+    let mut occupied_corners = 0;
+    let mut occupied_front_corners = 0;
+    for (x, y) in corners.iter() {
+        if cell_is_free(center.x + x, center.y + y, field) {
+            continue;
+        }
+
+        occupied_corners += 1;
+        // front corners lie on the same side as the pointing tile
+        if *x == pointing.0 || *y == pointing.1 {
+            occupied_front_corners += 1;
+        }
+    }
+
+    if occupied_corners < 3 {
+        return TSpin::None;
+    }
+
+    let (kick_x, kick_y) = last_rotation_kick;
+    if occupied_front_corners == 2 || (kick_x.abs() == 1 && kick_y.abs() == 2) {
+        TSpin::Full
+    } else {
+        TSpin::Mini
+    }
+}
+
+// Central block is the one touching all three others,
+// pointing side is the neighbour that has no pair on the opposite side
+fn find_center_and_pointing_side(figure: &Figure) -> Option<(Point, (isize, isize))> {
+    let center = *figure.tiles.iter().find(|tile| {
+        figure.tiles.iter().filter(|other| (other.x - tile.x).abs() + (other.y - tile.y).abs() == 1).count() == 3
+    })?;
+
+    let offsets: Vec<(isize, isize)> = figure.tiles.iter()
+        .map(|tile| (tile.x - center.x, tile.y - center.y))
+        .filter(|offset| *offset != (0, 0))
+        .collect();
+
+    let pointing = *offsets.iter().find(|(x, y)| !offsets.contains(&(-x, -y)))?;
+    Some((center, pointing))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tetronimoe::Direction;
+    use crate::rotation_system::RotationSystemType;
+
+    fn init_field(filled_cells: &[(usize, usize)]) -> Field {
+        let mut field = Field::init(10, 4);
+        for (x, y) in filled_cells {
+            field.set_filled(*x, *y, true);
+        }
+        field
+    }
+
+    fn init_figure(block_type: BlockType, direction: Direction, x: isize, y: isize) -> Figure {
+        Figure::init(block_type, direction, Point {x, y}, RotationSystemType::Srs.create().as_ref())
+    }
+
+    #[test]
+    fn full() {
+        // T points down, both corners under it are occupied
+        let figure = init_figure(BlockType::TBlock, Direction::Down, 4, 2);
+        let field = init_field(&[(3, 1), (3, 3), (5, 3)]);
+        assert_eq!(detect_tspin(&figure, true, (0, 0), &field), TSpin::Full);
+        assert_eq!(detect_tspin(&figure, true, (1, 0), &field), TSpin::Full);
+    }
+
+    #[test]
+    fn mini() {
+        // only one corner on the pointing side
+        let figure = init_figure(BlockType::TBlock, Direction::Down, 4, 2);
+        let field = init_field(&[(3, 1), (5, 1), (3, 3)]);
+        assert_eq!(detect_tspin(&figure, true, (0, 0), &field), TSpin::Mini);
+        assert_eq!(detect_tspin(&figure, true, (-1, 0), &field), TSpin::Mini);
+        assert_eq!(detect_tspin(&figure, true, (-2, 1), &field), TSpin::Mini);
+
+        // the far kick makes it full
+        assert_eq!(detect_tspin(&figure, true, (1, 2), &field), TSpin::Full);
+        assert_eq!(detect_tspin(&figure, true, (-1, -2), &field), TSpin::Full);
+    }
+
+    #[test]
+    fn walls_and_floor_are_corners() {
+        let figure = init_figure(BlockType::TBlock, Direction::Right, 0, 2);
+        assert_eq!(detect_tspin(&figure, true, (0, 0), &init_field(&[(1, 3)])), TSpin::Mini);
+
+        let figure = init_figure(BlockType::TBlock, Direction::Up, 4, 3);
+        assert_eq!(detect_tspin(&figure, true, (0, 0), &init_field(&[(3, 2)])), TSpin::Mini);
+        assert_eq!(detect_tspin(&figure, true, (0, 0), &init_field(&[(3, 2), (5, 2)])), TSpin::Full);
+    }
+
+    #[test]
+    fn no_spin() {
+        let figure = init_figure(BlockType::TBlock, Direction::Down, 4, 2);
+        let field = init_field(&[(3, 1), (3, 3), (5, 3)]);
+        // last move was not a rotation
+        assert_eq!(detect_tspin(&figure, false, (0, 0), &field), TSpin::None);
+        // two corners are not enough
+        assert_eq!(detect_tspin(&figure, true, (1, 2), &init_field(&[(3, 3), (5, 3)])), TSpin::None);
+
+        let figure = init_figure(BlockType::LBlock, Direction::Down, 4, 2);
+        assert_eq!(detect_tspin(&figure, true, (0, 0), &field), TSpin::None);
+    }
+}
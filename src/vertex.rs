@@ -2,4 +2,6 @@
 pub struct Vertex {
     pub position: [f32; 3],
     pub texture: [f32; 2],
-}
\ No newline at end of file
+}
+
+implement_vertex!(Vertex, position, texture);